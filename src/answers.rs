@@ -0,0 +1,211 @@
+use crate::parse::{parse_token, ParseError};
+use crate::runner::RunResult;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Known-good answers for the real inputs, keyed by (day, part). Stored as a small subset of
+/// TOML so it can be edited by hand:
+///
+/// ```toml
+/// [day1]
+/// part1 = 2264607
+/// part2 = "some text answer"
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Answers(BTreeMap<(u32, u32), String>);
+
+impl Answers {
+    pub fn parse(input: &str) -> Result<Answers, ParseError> {
+        let mut answers = BTreeMap::new();
+        let mut day = None;
+
+        for line in input.lines() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            if let Some(header) = trimmed.strip_prefix('[') {
+                let name = header
+                    .strip_suffix(']')
+                    .ok_or_else(|| ParseError::missing(input, line, "']'"))?;
+                let num = name
+                    .strip_prefix("day")
+                    .ok_or_else(|| ParseError::at(input, name, "expected a [dayN] section"))?;
+                day = Some(parse_token(input, num)?);
+                continue;
+            }
+
+            let (key, value) = trimmed
+                .split_once('=')
+                .ok_or_else(|| ParseError::at(input, trimmed, "expected partN = answer"))?;
+            let (key, value) = (key.trim(), value.trim());
+            let part = key
+                .strip_prefix("part")
+                .ok_or_else(|| ParseError::at(input, key, "expected partN"))?;
+            let part = parse_token(input, part)?;
+            let day = day
+                .ok_or_else(|| ParseError::at(input, key, "answer outside of a [dayN] section"))?;
+
+            // Anything after the value can be a comment, like TOML
+            let value = match value.strip_prefix('"') {
+                Some(quoted) => {
+                    let (quoted, rest) = quoted
+                        .split_once('"')
+                        .ok_or_else(|| ParseError::missing(input, value, "a closing '\"'"))?;
+                    let rest = rest.trim_start();
+                    if !rest.is_empty() && !rest.starts_with('#') {
+                        return Err(ParseError::at(input, rest, "expected a comment"));
+                    }
+                    quoted
+                }
+                None => value
+                    .find(" #")
+                    .or_else(|| value.find("\t#"))
+                    .map_or(value, |end| value[..end].trim_end()),
+            };
+
+            answers.insert((day, part), value.to_string());
+        }
+
+        Ok(Answers(answers))
+    }
+
+    pub fn get(&self, day: u32, part: u32) -> Option<&str> {
+        self.0.get(&(day, part)).map(|s| s.as_str())
+    }
+
+    pub fn check(&self, result: &RunResult) -> Verdict {
+        match (&result.answer, self.get(result.day, result.part)) {
+            (Err(e), _) => Verdict::Error(e.clone()),
+            (Ok(actual), None) => Verdict::Missing(actual.clone()),
+            (Ok(actual), Some(expected)) if actual == expected => Verdict::Pass,
+            (Ok(actual), Some(expected)) => Verdict::Fail {
+                expected: expected.to_string(),
+                actual: actual.clone(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Pass,
+    Fail {
+        expected: String,
+        actual: String,
+    },
+    /// Nothing recorded yet, carries what the solver produced so it can be pasted in
+    Missing(String),
+    Error(String),
+}
+
+impl Verdict {
+    pub fn is_failure(&self) -> bool {
+        matches!(self, Verdict::Fail { .. } | Verdict::Error(_))
+    }
+}
+
+pub fn format_report(checked: &[(&RunResult, Verdict)]) -> String {
+    let mut out = String::new();
+    let (mut passed, mut failed, mut missing) = (0, 0, 0);
+
+    for (result, verdict) in checked {
+        let detail = match verdict {
+            Verdict::Pass => {
+                passed += 1;
+                "pass".to_string()
+            }
+            Verdict::Fail { expected, actual } => {
+                failed += 1;
+                format!("FAIL expected {} got {}", expected, actual)
+            }
+            Verdict::Missing(actual) => {
+                missing += 1;
+                format!("missing (got {})", actual)
+            }
+            Verdict::Error(e) => {
+                failed += 1;
+                format!("FAIL {}", e)
+            }
+        };
+        writeln!(
+            out,
+            "Day {:>2} Part {}: {}",
+            result.day, result.part, detail
+        )
+        .unwrap();
+    }
+    writeln!(
+        out,
+        "{} passed, {} failed, {} missing",
+        passed, failed, missing
+    )
+    .unwrap();
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::time::Duration;
+
+    fn get_test_input() -> &'static str {
+        "# Known answers
+[day1]
+part1 = 11
+part2 = \"31\" # quoted
+
+[day6]
+part1 = 41 # the example
+"
+    }
+
+    fn result(day: u32, part: u32, answer: &str) -> RunResult {
+        RunResult {
+            day,
+            part,
+            answer: Ok(answer.to_string()),
+            parse_time: Duration::ZERO,
+            solve_time: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn answers_parse() {
+        let answers = Answers::parse(get_test_input()).unwrap();
+        assert_eq!(answers.get(1, 1), Some("11"));
+        assert_eq!(answers.get(1, 2), Some("31"));
+        assert_eq!(answers.get(6, 1), Some("41"));
+        assert_eq!(answers.get(6, 2), None);
+
+        let err = Answers::parse("[day1]\npart1 11").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+        let err = Answers::parse("[day1]\npart1 = \"11\" 12").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 14));
+    }
+
+    #[test]
+    fn answers_verify() {
+        let answers = Answers::parse(get_test_input()).unwrap();
+        let results = [result(1, 1, "11"), result(1, 2, "32"), result(6, 2, "6")];
+        let checked = results
+            .iter()
+            .map(|r| (r, answers.check(r)))
+            .collect::<Vec<_>>();
+
+        assert_eq!(checked[0].1, Verdict::Pass);
+        assert!(checked[1].1.is_failure());
+        assert_eq!(checked[2].1, Verdict::Missing("6".to_string()));
+        assert_eq!(
+            format_report(&checked),
+            "Day  1 Part 1: pass
+Day  1 Part 2: FAIL expected 31 got 32
+Day  6 Part 2: missing (got 6)
+1 passed, 1 failed, 1 missing
+"
+        );
+    }
+}
@@ -1,20 +1,25 @@
+use crate::parse::{parse_token, ParseError};
 use std::collections::BTreeMap;
 
 type InputType = (Vec<i32>, Vec<i32>);
 type OutputType = i32;
 
 #[aoc_generator(day1)]
-fn day1_parse(input: &str) -> InputType {
+fn day1_parse(input: &str) -> Result<InputType, ParseError> {
     input
         .lines()
         .map(|line| {
             let mut iter = line.split_whitespace();
-            (
-                iter.next().unwrap().parse::<i32>().unwrap(),
-                iter.next().unwrap().parse::<i32>().unwrap(),
-            )
+            let mut next = |what| {
+                let token = iter
+                    .next()
+                    .ok_or_else(|| ParseError::missing(input, line, what))?;
+                parse_token::<i32>(input, token)
+            };
+            Ok((next("a left number")?, next("a right number")?))
         })
-        .unzip()
+        .collect::<Result<Vec<_>, _>>()
+        .map(|pairs| pairs.into_iter().unzip())
 }
 
 #[aoc(day1, part1)]
@@ -63,11 +68,20 @@ mod tests {
 
     #[test]
     fn day1_part1() {
-        assert_eq!(part1(&day1_parse(get_test_input())), 11);
+        assert_eq!(part1(&day1_parse(get_test_input()).unwrap()), 11);
     }
 
     #[test]
     fn day1_part2() {
-        assert_eq!(part2(&day1_parse(get_test_input())), 31);
+        assert_eq!(part2(&day1_parse(get_test_input()).unwrap()), 31);
+    }
+
+    #[test]
+    fn day1_parse_errors() {
+        let err = day1_parse("3   4\n4   x3").unwrap_err();
+        assert_eq!((err.line, err.column, err.snippet.as_str()), (2, 5, "x3"));
+
+        let err = day1_parse("3   4\n4").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 2));
     }
 }
@@ -1,17 +1,22 @@
+use crate::geometry::Point;
+use crate::grid::Grid;
+use crate::parse::ParseError;
+use crate::render::Render;
+use log::{debug, trace};
 use std::collections::BTreeSet;
+use std::fmt;
 use std::ops::{Deref, DerefMut};
-use std::{collections::BTreeMap, fmt};
 
-pub struct Map(BTreeMap<Point, Height>);
+//'.' shows up in the examples for cells that aren't part of any trail
+pub struct Map(Grid<Option<Height>>);
 
-type Point = (i64, i64);
 type Height = i8;
 
 type InputType = Map;
 type OutputType = u64;
 
 impl Deref for Map {
-    type Target = BTreeMap<Point, Height>;
+    type Target = Grid<Option<Height>>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -25,76 +30,55 @@ impl DerefMut for Map {
 }
 
 impl Map {
-    fn max_x(&self) -> i64 {
-        self.iter().map(|((x, _), _)| *x).max().unwrap()
-    }
-    fn max_y(&self) -> i64 {
-        self.iter().map(|((_, y), _)| *y).max().unwrap()
+    fn height_at(&self, p: Point) -> Option<Height> {
+        self.get(p).copied().flatten()
     }
 
     fn find_adjacent_with_plus_one(&self, p: &Point) -> Vec<Point> {
-        let mut adjacent = Vec::new();
-        let (x, y) = p;
-        for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)].iter() {
-            let new_p = (x + dx, y + dy);
-            if let Some(h) = self.get(&new_p) {
-                if *h == self.get(p).unwrap() + 1 {
-                    adjacent.push(new_p);
-                }
-            }
-        }
-        adjacent
+        let current = self.height_at(*p).unwrap();
+        self.neighbours4(*p)
+            .filter(|(_, h)| **h == Some(current + 1))
+            .map(|(new_p, _)| new_p)
+            .collect()
     }
 }
 
 impl fmt::Display for Map {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for y in 0..=self.max_y() {
-            for x in 0..=self.max_x() {
-                match self.get(&(x, y)) {
-                    Some(h) => write!(f, "{}", h)?,
-                    None => write!(f, ".")?,
-                }
-            }
-            writeln!(f)?;
-        }
-        write!(f, "")
+        let rendered = Render::new(&self.0).colour(false).render(|h| match h {
+            Some(h) => char::from_digit(*h as u32, 10).unwrap_or('?'),
+            None => '.',
+        });
+        write!(f, "{}", rendered)
     }
 }
 
 #[aoc_generator(day10)]
-fn day10_parse(input: &str) -> InputType {
-    input
-        .lines()
-        .enumerate()
-        .fold(Map(BTreeMap::new()), |mut map, (y, line)| {
-            line.chars().enumerate().for_each(|(x, c)| {
-                if c == '.' {
-                    return; //tests
-                }
-                map.insert((x as i64, y as i64), c.to_digit(10).unwrap() as i8);
-            });
-
-            map
-        })
+fn day10_parse(input: &str) -> Result<InputType, ParseError> {
+    Grid::try_parse(input, |c| match c {
+        '.' => Ok(None), //tests
+        c => c
+            .to_digit(10)
+            .map(|h| Some(h as Height))
+            .ok_or_else(|| format!("Invalid height '{}'", c)),
+    })
+    .map(Map)
 }
 
 impl Map {
     fn find_trailheads(&self) -> Vec<Point> {
         self.iter()
-            .filter(|(_, v)| **v == 0)
-            .map(|(p, _)| *p)
+            .filter(|(_, v)| **v == Some(0))
+            .map(|(p, _)| p)
             .collect::<Vec<Point>>()
     }
 
     fn solve(&self, part2: bool) -> u64 {
-        #[cfg(test)]
-        print!("{}", self);
+        trace!("\n{}", self);
         //Find trailheads
         let trailheads = self.find_trailheads();
 
-        #[cfg(test)]
-        println!("{:?}", trailheads);
+        debug!("trailheads: {:?}", trailheads);
 
         // Just.... DFS?
         trailheads
@@ -110,7 +94,7 @@ impl Map {
                     let adjacents = self.find_adjacent_with_plus_one(&node);
 
                     for adj in adjacents {
-                        if *self.get(&adj).unwrap() == 9 {
+                        if self.height_at(adj) == Some(9) {
                             completed_trails.insert(adj);
                             //For part2, we care about unique trails, in part 1, I was throwing this
                             //out, since I didn't care that multiple unique trails hit the same end
@@ -185,28 +169,28 @@ mod tests {
 .....01"
     }
 
-    #[test]
+    #[test_log::test]
     fn day10_part1_simple() {
-        assert_eq!(part1(&day10_parse(get_simple_test_input())), 1);
+        assert_eq!(part1(&day10_parse(get_simple_test_input()).unwrap()), 1);
     }
 
-    #[test]
+    #[test_log::test]
     fn day10_part1_simple2() {
-        assert_eq!(part1(&day10_parse(get_simple_test_input2())), 2);
+        assert_eq!(part1(&day10_parse(get_simple_test_input2()).unwrap()), 2);
     }
 
-    #[test]
+    #[test_log::test]
     fn day10_part1_simple3() {
-        assert_eq!(part1(&day10_parse(get_simple_test_input3())), 3);
+        assert_eq!(part1(&day10_parse(get_simple_test_input3()).unwrap()), 3);
     }
 
-    #[test]
+    #[test_log::test]
     fn day10_part1() {
-        assert_eq!(part1(&day10_parse(get_test_input())), 36);
+        assert_eq!(part1(&day10_parse(get_test_input()).unwrap()), 36);
     }
 
-    #[test]
+    #[test_log::test]
     fn day10_part2() {
-        assert_eq!(part2(&day10_parse(get_test_input())), 81);
+        assert_eq!(part2(&day10_parse(get_test_input()).unwrap()), 81);
     }
 }
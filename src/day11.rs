@@ -1,3 +1,4 @@
+use crate::parse::{parse_token, ParseError};
 use memoize::memoize;
 use std::{
     collections::BTreeMap,
@@ -25,10 +26,10 @@ impl DerefMut for Stone {
 }
 
 #[aoc_generator(day11)]
-fn day11_parse(input: &str) -> InputType {
+fn day11_parse(input: &str) -> Result<InputType, ParseError> {
     input
-        .split(" ")
-        .map(|x| Stone(x.parse::<i64>().unwrap()))
+        .split_whitespace()
+        .map(|x| parse_token(input, x).map(Stone))
         .collect()
 }
 
@@ -42,7 +43,7 @@ fn apply_rules(stone: Stone) -> (Option<Stone>, Option<Stone>) {
         return (Some(Stone(1)), None);
     }
     //If even number of digits, split in half (stringwise), and return the two halves (dropping leading 0s)
-    if val.to_string().len() % 2 == 0 {
+    if val.to_string().len().is_multiple_of(2) {
         let s = val.to_string();
         let half = s.len() / 2;
         let (left, right) = s.split_at(half);
@@ -114,6 +115,6 @@ mod tests {
 
     #[test]
     fn day11_part1() {
-        assert_eq!(part1(&day11_parse(get_test_input())), 55312);
+        assert_eq!(part1(&day11_parse(get_test_input()).unwrap()), 55312);
     }
 }
@@ -1,11 +1,13 @@
+use crate::parse::{parse_token, ParseError};
+
 type InputType = Vec<Vec<i64>>;
 type OutputType = u64;
 
 #[aoc_generator(day2)]
-fn day1_parse(input: &str) -> InputType {
+fn day1_parse(input: &str) -> Result<InputType, ParseError> {
     input
         .lines()
-        .map(|l| l.split(' ').map(|n| n.parse().unwrap()).collect())
+        .map(|l| l.split(' ').map(|n| parse_token(input, n)).collect())
         .collect()
 }
 
@@ -97,11 +99,11 @@ mod tests {
 
     #[test]
     fn day2_part1() {
-        assert_eq!(part1(&day1_parse(get_test_input())), 2);
+        assert_eq!(part1(&day1_parse(get_test_input()).unwrap()), 2);
     }
 
     #[test]
     fn day2_part2() {
-        assert_eq!(part2(&day1_parse(get_test_input())), 4);
+        assert_eq!(part2(&day1_parse(get_test_input()).unwrap()), 4);
     }
 }
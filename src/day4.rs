@@ -1,37 +1,14 @@
-use std::collections::BTreeMap;
+use crate::geometry::{Direction, Point};
+use crate::grid::Grid;
+use crate::parse::ParseError;
+use crate::render::{Layer, Render};
 
-type InputType = BTreeMap<(i32, i32), char>;
+type InputType = Grid<char>;
 type OutputType = u64;
 
 #[aoc_generator(day4)]
-fn day4_parse(input: &str) -> InputType {
-    let mut set = BTreeMap::new();
-    for (x, line) in input.lines().enumerate() {
-        for (y, c) in line.chars().enumerate() {
-            set.insert((x as i32, y as i32), c);
-        }
-    }
-
-    set
-}
-
-fn look_for_letter(input: &InputType, x: i32, y: i32, letter: char) -> Vec<(i32, i32)> {
-    let mut vecs = Vec::new();
-    for dx in -1..=1 {
-        for dy in -1..=1 {
-            if dx == 0 && dy == 0 {
-                continue;
-            }
-
-            if let Some(c) = input.get(&(x + dx, y + dy)) {
-                if c == &letter {
-                    vecs.push((x + dx, y + dy));
-                }
-            }
-        }
-    }
-
-    vecs
+fn day4_parse(input: &str) -> Result<InputType, ParseError> {
+    Grid::try_parse(input, Ok)
 }
 
 #[cfg(debug_assertions)]
@@ -39,49 +16,31 @@ use std::collections::BTreeSet;
 
 #[allow(dead_code)]
 #[cfg(debug_assertions)]
-fn dump_map(input: &InputType, highlights: BTreeSet<(i32, i32)>) {
-    println!("dumping map");
-    let max_x = *input.keys().map(|(x, _)| x).max().unwrap();
-    let max_y = *input.keys().map(|(_, y)| y).max().unwrap();
-    for x in 0..=max_x {
-        for y in 0..=max_y {
-            if highlights.contains(&(x, y)) {
-                print!("{}", input.get(&(x, y)).unwrap().to_uppercase());
-            } else if highlights.is_empty() {
-                print!("{}", input.get(&(x, y)).unwrap());
-            } else {
-                print!(".");
-            }
-        }
-        println!();
+fn dump_map(input: &InputType, highlights: BTreeSet<Point>) {
+    let mut render = Render::new(input);
+    if !highlights.is_empty() {
+        render = render
+            .layer(Layer::new(highlights).colour(colored::Color::Green))
+            .unmarked('.');
     }
+    log::trace!("dumping map\n{}", render.render(|c| *c));
 }
 
 #[aoc(day4, part1)]
 pub fn part1(input: &InputType) -> OutputType {
-    //Iterate through the entire map, looking for X, if you find X, look in all directions for M, if you find an M, go ahead and check the down that path to see if it matches up
-
+    //Iterate through the entire map, looking for X, if you find X, look in all directions to see
+    //if the rest of the word is spelled out along that ray
     let mut count = 0;
-    for ((x, y), c) in input.iter() {
+    for (p, c) in input.iter() {
         if *c == 'X' {
-            let m_possibilities = look_for_letter(input, *x, *y, 'M');
-            //Now that we have all the M possibilities, we need to look for A, but ONLY in the direction of the M
-            for (m_pos_x, m_pos_y) in m_possibilities {
-                let dx = m_pos_x + x * -1;
-                let dy = m_pos_y + y * -1;
-                // dx, dy contains the direction of the M
-                if let Some(c) = input.get(&(m_pos_x + dx, m_pos_y + dy)) {
-                    if *c == 'A' {
-                        let a_pos_x = m_pos_x + dx;
-                        let a_pos_y = m_pos_y + dy;
-                        let dx = a_pos_x - m_pos_x;
-                        let dy = a_pos_y - m_pos_y;
-                        if let Some(c) = input.get(&(a_pos_x + dx, a_pos_y + dy)) {
-                            if *c == 'S' {
-                                count += 1;
-                            }
-                        }
-                    }
+            for dir in Direction::ALL {
+                if input
+                    .ray(p, dir.vector())
+                    .map(|(_, c)| *c)
+                    .take(4)
+                    .eq("XMAS".chars())
+                {
+                    count += 1;
                 }
             }
         }
@@ -93,12 +52,12 @@ pub fn part1(input: &InputType) -> OutputType {
 pub fn part2(input: &InputType) -> OutputType {
     let mut count = 0;
     // Now we need to find the Crosses, but the letters can be jumbled. To do this, we can find all A's in the map, and then check the diagnols for M's and S's.
-    for ((x, y), c) in input.iter() {
+    for (p, c) in input.iter() {
         if *c == 'A' {
-            let bottom_left = input.get(&(x - 1, y + 1));
-            let bottom_right = input.get(&(x + 1, y + 1));
-            let top_left = input.get(&(x - 1, y - 1));
-            let top_right = input.get(&(x + 1, y - 1));
+            let bottom_left = input.get(p.step(Direction::SouthWest));
+            let bottom_right = input.get(p.step(Direction::SouthEast));
+            let top_left = input.get(p.step(Direction::NorthWest));
+            let top_right = input.get(p.step(Direction::NorthEast));
 
             if bottom_left.is_none()
                 || bottom_right.is_none()
@@ -147,13 +106,13 @@ MAMMMXMMMM
 MXMXAXMASX"
     }
 
-    #[test]
+    #[test_log::test]
     fn day4_part1() {
-        assert_eq!(part1(&day4_parse(get_test_input())), 18);
+        assert_eq!(part1(&day4_parse(get_test_input()).unwrap()), 18);
     }
 
-    #[test]
+    #[test_log::test]
     fn day4_part2() {
-        assert_eq!(part2(&day4_parse(get_test_input())), 9);
+        assert_eq!(part2(&day4_parse(get_test_input()).unwrap()), 9);
     }
 }
@@ -1,36 +1,237 @@
-use std::collections::BTreeMap;
+use crate::input::sections;
+use crate::parse::{parse_token, ParseError};
+use log::{debug, warn};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::error::Error;
+use std::fmt::{self, Write};
 
 type InputType = (BTreeMap<u64, Vec<u64>>, Vec<Updates>);
 type OutputType = u64;
 
 type Updates = Vec<u64>;
 
+/// The rules between some of an update's pages go round in a circle, so nothing can go first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoOrdering {
+    pub update: Vec<u64>,
+    /// Pages that couldn't be placed, in their original order
+    pub stuck: Vec<u64>,
+}
+
+impl fmt::Display for NoOrdering {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "no order of {:?} satisfies the rules, stuck on {:?}",
+            self.update, self.stuck
+        )
+    }
+}
+
+impl Error for NoOrdering {}
+
+/// A rule an update breaks, `before` has to come before `after` but doesn't
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Violation {
+    pub before: u64,
+    pub after: u64,
+    /// Where they are in the update
+    pub before_idx: usize,
+    pub after_idx: usize,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}|{}: {} is at index {}, after {} at index {}",
+            self.before, self.after, self.before, self.before_idx, self.after, self.after_idx
+        )
+    }
+}
+
+/// Everything suspicious about a set of rules and updates, see `validate`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Validation {
+    /// The shortest cycle anywhere in the rules, each page has to come before the next and the
+    /// last before the first. Harmless as long as no single update contains one
+    pub cycle: Option<Vec<u64>>,
+    /// (update index, shortest cycle among its pages), these updates can't be put in order
+    pub update_cycles: Vec<(usize, Vec<u64>)>,
+    /// Rules that appear more than once, as (before, after)
+    pub duplicates: Vec<(u64, u64)>,
+    /// Pages in the updates that no rule mentions
+    pub unruled: Vec<u64>,
+    /// Updates with no middle page, the default `Score::UpperMedian` takes the upper of the two
+    pub even_updates: Vec<usize>,
+}
+
+impl Validation {
+    /// Nothing that would give a wrong answer
+    pub fn is_ok(&self) -> bool {
+        self.update_cycles.is_empty()
+    }
+
+    /// Nothing a puzzle-shaped input wouldn't have
+    fn is_puzzle_shaped(&self) -> bool {
+        self.is_ok() && self.even_updates.is_empty()
+    }
+}
+
+fn fmt_cycle(cycle: &[u64]) -> String {
+    let pages = cycle.iter().chain(&cycle[..1]).map(|p| p.to_string());
+    pages.collect::<Vec<_>>().join(" -> ")
+}
+
+impl fmt::Display for Validation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut problems = Vec::new();
+        if let Some(cycle) = &self.cycle {
+            problems.push(format!("rules have a cycle: {}", fmt_cycle(cycle)));
+        }
+        for (idx, cycle) in &self.update_cycles {
+            problems.push(format!("update {} has a cycle: {}", idx, fmt_cycle(cycle)));
+        }
+        for (before, after) in &self.duplicates {
+            problems.push(format!("duplicate rule {}|{}", before, after));
+        }
+        for page in &self.unruled {
+            problems.push(format!("no rules for page {}", page));
+        }
+        for idx in &self.even_updates {
+            problems.push(format!("update {} has an even number of pages", idx));
+        }
+
+        if problems.is_empty() {
+            write!(f, "rules and updates look fine")
+        } else {
+            write!(f, "{}", problems.join("\n"))
+        }
+    }
+}
+
 #[aoc_generator(day5)]
-fn day5_parse(input: &str) -> InputType {
-    let mut sections = input.split("\n\n");
-
-    (
-        sections
-            .next()
-            .unwrap()
-            .lines()
-            .fold(BTreeMap::new(), |mut acc, l| {
-                let nums = l.split("|").map(|n| n.parse().unwrap()).collect::<Vec<_>>();
-                acc.entry(nums[0]).or_default().push(nums[1]);
-                acc
-            }),
-        sections
-            .next()
-            .unwrap()
-            .lines()
-            .map(|l| l.split(",").map(|n| n.parse().unwrap()).collect())
+pub fn day5_parse(input: &str) -> Result<InputType, ParseError> {
+    let mut sections = sections(input);
+
+    let rules_section = sections
+        .next()
+        .ok_or_else(|| ParseError::missing(input, input, "some ordering rules"))?;
+    let updates_section = sections
+        .next()
+        .ok_or_else(|| ParseError::missing(input, input, "a blank line before the updates"))?;
+
+    let mut rules: BTreeMap<u64, Vec<u64>> = BTreeMap::new();
+    for l in rules_section.lines() {
+        let (before, after) = l
+            .split_once('|')
+            .ok_or_else(|| ParseError::at(input, l, "expected a rule like 47|53"))?;
+        rules
+            .entry(parse_token(input, before)?)
+            .or_default()
+            .push(parse_token(input, after)?);
+    }
+
+    let updates: Vec<Updates> = updates_section
+        .lines()
+        .map(|l| l.split(',').map(|n| parse_token(input, n)).collect())
+        .collect::<Result<_, _>>()?;
+
+    let validation = validate(&rules, &updates);
+    if validation.is_puzzle_shaped() {
+        debug!("{}", validation);
+    } else {
+        warn!("{}", validation);
+    }
+
+    Ok((rules, updates))
+}
+
+/// Check the rules make sense, globally and for each update
+pub fn validate(rules: &BTreeMap<u64, Vec<u64>>, updates: &[Updates]) -> Validation {
+    let update_cycles = updates
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, update)| {
+            let pages = update.iter().copied().collect();
+            shortest_cycle(rules, Some(&pages)).map(|cycle| (idx, cycle))
+        })
+        .collect();
+
+    let mut seen = BTreeSet::new();
+    let mut duplicates = BTreeSet::new();
+    for (&before, afters) in rules {
+        for &after in afters {
+            if !seen.insert((before, after)) {
+                duplicates.insert((before, after));
+            }
+        }
+    }
+
+    let mentioned = seen
+        .iter()
+        .flat_map(|&(before, after)| [before, after])
+        .collect::<BTreeSet<_>>();
+    let unruled = updates
+        .iter()
+        .flatten()
+        .filter(|p| !mentioned.contains(p))
+        .copied()
+        .collect::<BTreeSet<_>>();
+
+    Validation {
+        cycle: shortest_cycle(rules, None),
+        update_cycles,
+        duplicates: duplicates.into_iter().collect(),
+        unruled: unruled.into_iter().collect(),
+        even_updates: (updates.iter().enumerate())
+            .filter(|(_, update)| update.len().is_multiple_of(2))
+            .map(|(idx, _)| idx)
             .collect(),
-    )
+    }
+}
+
+/// The shortest cycle in the rules between `pages` (all of them if None). Breadth first search
+/// from every page, the first time we get back to where we started is the shortest way round.
+fn shortest_cycle(
+    rules: &BTreeMap<u64, Vec<u64>>,
+    pages: Option<&BTreeSet<u64>>,
+) -> Option<Vec<u64>> {
+    let allowed = |p: &u64| pages.is_none_or(|pages| pages.contains(p));
+    let mut best: Option<Vec<u64>> = None;
+
+    for &start in rules.keys().filter(|p| allowed(p)) {
+        let mut came_from = BTreeMap::new();
+        let mut queue = VecDeque::from([start]);
+
+        'search: while let Some(page) = queue.pop_front() {
+            for &next in rules.get(&page).into_iter().flatten() {
+                if next == start {
+                    //Back round, walk back to the start to get the path
+                    let mut cycle = vec![page];
+                    while let Some(&prev) = came_from.get(cycle.last().unwrap()) {
+                        cycle.push(prev);
+                    }
+                    cycle.reverse();
+                    if best.as_ref().is_none_or(|best| cycle.len() < best.len()) {
+                        best = Some(cycle);
+                    }
+                    break 'search;
+                }
+                if allowed(&next) && !came_from.contains_key(&next) {
+                    came_from.insert(next, page);
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+
+    best
 }
 
 fn is_valid(rules: &BTreeMap<u64, Vec<u64>>, updates: &[u64]) -> bool {
-    //Interesting note about the data, all the list lengths are odd (to be able to find the middle)
-    assert_eq!(updates.len() % 2, 1);
+    //Interesting note about the data, all the list lengths are odd (to be able to find the middle).
+    //Even ones are fine here, validate warns about them since they don't have a middle
 
     updates.iter().enumerate().all(|(idx, num)| {
         //First rule is always valid
@@ -57,8 +258,131 @@ fn is_valid(rules: &BTreeMap<u64, Vec<u64>>, updates: &[u64]) -> bool {
     })
 }
 
+/// Like `is_valid`, but every rule the update breaks instead of stopping at the first. Ordered
+/// by where the page that's too late is, then where the page it should have been before is.
+pub fn violations(rules: &BTreeMap<u64, Vec<u64>>, update: &[u64]) -> Vec<Violation> {
+    let mut found = Vec::new();
+    for (before_idx, before) in update.iter().enumerate() {
+        let rule = match rules.get(before) {
+            Some(r) => r,
+            None => continue,
+        };
+        for (after_idx, after) in update[0..before_idx].iter().enumerate() {
+            if rule.contains(after) {
+                found.push(Violation {
+                    before: *before,
+                    after: *after,
+                    before_idx,
+                    after_idx,
+                });
+            }
+        }
+    }
+    found
+}
+
+/// Explain why `update` is or isn't in the right order
+pub fn report(rules: &BTreeMap<u64, Vec<u64>>, update: &[u64]) -> String {
+    let pages = update.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+    let found = violations(rules, update);
+    if found.is_empty() {
+        return format!("update {} is in the right order\n", pages.join(","));
+    }
+
+    let mut out = format!(
+        "update {} breaks {} rule{}:\n",
+        pages.join(","),
+        found.len(),
+        if found.len() == 1 { "" } else { "s" }
+    );
+    for v in found {
+        writeln!(out, "  {}", v).unwrap();
+    }
+    out
+}
+
+/// The rules as a Graphviz digraph, an edge from each page to the pages that have to come after
+/// it. With an update, only its pages are drawn (labelled with where they are in it) and the
+/// rules it breaks are red.
+pub fn to_dot(rules: &BTreeMap<u64, Vec<u64>>, update: Option<&[u64]>) -> String {
+    let pages = update.map(|u| u.iter().copied().collect::<BTreeSet<_>>());
+    let broken = update
+        .map(|u| violations(rules, u))
+        .unwrap_or_default()
+        .iter()
+        .map(|v| (v.before, v.after))
+        .collect::<BTreeSet<_>>();
+
+    let mut out = "digraph rules {\n".to_string();
+    if let Some(update) = update {
+        for (idx, page) in update.iter().enumerate() {
+            writeln!(out, "    {} [label=\"{} ({})\"];", page, page, idx).unwrap();
+        }
+    }
+
+    //Sorted, and duplicate rules only drawn once
+    let edges = rules
+        .iter()
+        .flat_map(|(&before, afters)| afters.iter().map(move |&after| (before, after)))
+        .filter(|(before, after)| {
+            pages
+                .as_ref()
+                .is_none_or(|pages| pages.contains(before) && pages.contains(after))
+        })
+        .collect::<BTreeSet<_>>();
+    for (before, after) in edges {
+        let style = if broken.contains(&(before, after)) {
+            " [color=red, penwidth=2]"
+        } else {
+            ""
+        };
+        writeln!(out, "    {} -> {}{};", before, after, style).unwrap();
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+/// How to turn a correctly ordered update into a number
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Score {
+    LowerMedian,
+    /// The puzzle's rule, updates there are odd lengths so either median is the middle page
+    #[default]
+    UpperMedian,
+    First,
+    Last,
+    /// A 32 bit FNV-1a hash of the whole order, to tell whether two orderings agree everywhere
+    Hash,
+}
+
+impl Score {
+    /// Empty updates score 0
+    pub fn score(self, update: &[u64]) -> u64 {
+        if update.is_empty() {
+            return 0;
+        }
+        match self {
+            Score::UpperMedian => update[update.len() / 2],
+            Score::LowerMedian => update[(update.len() - 1) / 2],
+            Score::First => update[0],
+            Score::Last => update[update.len() - 1],
+            Score::Hash => update
+                .iter()
+                .flat_map(|page| page.to_le_bytes())
+                .fold(0x811c9dc5u32, |hash, byte| {
+                    (hash ^ byte as u32).wrapping_mul(0x01000193)
+                }) as u64,
+        }
+    }
+}
+
 #[aoc(day5, part1)]
 pub fn part1(input: &InputType) -> OutputType {
+    part1_with(input, Score::default())
+}
+
+pub fn part1_with(input: &InputType, score: Score) -> OutputType {
     let rules = &input.0;
     let updates = &input.1;
 
@@ -67,64 +391,63 @@ pub fn part1(input: &InputType) -> OutputType {
         .iter()
         //yes, I know filter_map exists
         .filter(|update| is_valid(rules, update))
-        .map(|update| update.get(update.len() / 2).unwrap())
+        .map(|update| score.score(update))
         .sum::<u64>()
 }
 
 #[aoc(day5, part2)]
-pub fn part2(input: &InputType) -> OutputType {
+pub fn part2(input: &InputType) -> Result<OutputType, NoOrdering> {
+    part2_with(input, Score::default())
+}
+
+pub fn part2_with(input: &InputType, score: Score) -> Result<OutputType, NoOrdering> {
     let rules = &input.0;
     let updates = &input.1;
 
     updates
         .iter()
         .filter(|update| !is_valid(rules, update))
-        //fix_update short circuits and just gives me the middle value
-        .map(|update| fix_update_and_find_mid(rules, update))
-        .sum::<u64>()
+        .map(|update| reorder(rules, update).map(|fixed| score.score(&fixed)))
+        .sum()
 }
 
-fn fix_update_and_find_mid(rules: &BTreeMap<u64, Vec<u64>>, update: &[u64]) -> u64 {
-    let mut update = update.to_owned();
-
-    //We have all the rules, fix them by moving the value in front of all the rules it's violating,
-    //you can always move left, so find the one that moves it the furthest left that satisfies the
-    //rule without just moving it to 0th
-
-    // Do that by determining if a particular rule is violated, if it is, find the index of all of
-    // the places it's violating, since we only care about the left most (as moving right will
-    // always make it violate even more) then just find the lowest (left-most) that we can put the
-    // value that satisfies all the rules.
-
-    for (idx, num) in update.clone().iter().enumerate() {
-        let rule = match rules.get(num) {
-            Some(r) => r,
-            None => &vec![], //If there are no rules, we can't be violating them
-        };
-
-        //It only makes sense to check just the ones to the left of where we are, because those are
-        //the only ones we can be violating.
-        let previous = &update[0..idx];
-        let mut violation_indexes = vec![];
-
-        for (v_idx, p) in previous.iter().enumerate() {
-            if rule.contains(p) {
-                violation_indexes.push(v_idx);
+/// Put the pages of `update` in an order that satisfies every rule between them (a topological
+/// sort of the rules restricted to these pages). Pages that don't have a rule between them stay
+/// in their original order.
+pub fn reorder(rules: &BTreeMap<u64, Vec<u64>>, update: &[u64]) -> Result<Vec<u64>, NoOrdering> {
+    //How many of the other pages have to come before each page. Rules about pages that aren't
+    //in the update don't matter
+    let mut must_follow: BTreeMap<u64, usize> = update.iter().map(|&p| (p, 0)).collect();
+    for page in update {
+        for after in rules.get(page).into_iter().flatten() {
+            if let Some(count) = must_follow.get_mut(after) {
+                *count += 1;
             }
         }
+    }
 
-        if !violation_indexes.is_empty() {
-            //We know we are violating the rule
-            let lowest = violation_indexes.iter().min().unwrap(); //Find the lowest violator
-                                                                  //Insert the value right to the left of the lowest violator (remove first!)
-            update.remove(idx);
-            //update.insert(*lowest, *num);
-            update.insert(*lowest, *num);
+    //Keep taking the first page that nothing left has to come before
+    let mut remaining = update.to_vec();
+    let mut order = Vec::with_capacity(update.len());
+    while let Some(idx) = remaining.iter().position(|p| must_follow[p] == 0) {
+        let page = remaining.remove(idx);
+        for after in rules.get(&page).into_iter().flatten() {
+            if let Some(count) = must_follow.get_mut(after) {
+                *count -= 1;
+            }
         }
+        order.push(page);
     }
 
-    //Since we know we only care about the middle digit, just return it here instead of doing it elsewhere
-    update[update.len() / 2]
+    //Anything left over is waiting on a page that's waiting on it
+    if remaining.is_empty() {
+        Ok(order)
+    } else {
+        Err(NoOrdering {
+            update: update.to_vec(),
+            stuck: remaining,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -163,26 +486,186 @@ mod tests {
 97,13,75,29,47"
     }
 
-    #[test]
+    #[test_log::test]
     fn day5_part2_test_single() {
-        let (rules, _) = day5_parse(get_test_input());
+        let (rules, _) = day5_parse(get_test_input()).unwrap();
 
         let update = vec![61, 13, 29];
         assert!(!is_valid(&rules, &update));
-        assert_eq!(fix_update_and_find_mid(&rules, &update), 29);
+        assert_eq!(reorder(&rules, &update), Ok(vec![61, 29, 13]));
 
         let update = vec![75, 97, 47, 61, 53];
         assert!(!is_valid(&rules, &update));
-        assert_eq!(fix_update_and_find_mid(&rules, &update), 47);
+        assert_eq!(reorder(&rules, &update), Ok(vec![97, 75, 47, 61, 53]));
+
+        let update = vec![97, 13, 75, 29, 47];
+        assert_eq!(reorder(&rules, &update), Ok(vec![97, 75, 47, 29, 13]));
     }
 
-    #[test]
+    #[test_log::test]
+    fn day5_reorder_impossible() {
+        let (rules, _) = day5_parse("1|2\n2|3\n3|1\n4|1\n\n1,2,3").unwrap();
+
+        //Only the pages in the update matter, without 3 there's no circle
+        assert_eq!(reorder(&rules, &[2, 4, 1]), Ok(vec![4, 1, 2]));
+
+        let err = reorder(&rules, &[4, 3, 2, 1, 5]).unwrap_err();
+        assert_eq!(err.stuck, vec![3, 2, 1]);
+        assert_eq!(
+            err.to_string(),
+            "no order of [4, 3, 2, 1, 5] satisfies the rules, stuck on [3, 2, 1]"
+        );
+
+        assert!(part2(&(rules, vec![vec![3, 2, 1]])).is_err());
+    }
+
+    #[test_log::test]
     fn day5_part1() {
-        assert_eq!(part1(&day5_parse(get_test_input())), 143);
+        assert_eq!(part1(&day5_parse(get_test_input()).unwrap()), 143);
     }
 
-    #[test]
+    #[test_log::test]
     fn day5_part2() {
-        assert_eq!(part2(&day5_parse(get_test_input())), 123);
+        assert_eq!(part2(&day5_parse(get_test_input()).unwrap()).unwrap(), 123);
+    }
+
+    #[test_log::test]
+    fn day5_violations() {
+        let (rules, updates) = day5_parse(get_test_input()).unwrap();
+        for update in &updates {
+            assert_eq!(
+                is_valid(&rules, update),
+                violations(&rules, update).is_empty()
+            );
+        }
+
+        let violation = |before, after, before_idx, after_idx| Violation {
+            before,
+            after,
+            before_idx,
+            after_idx,
+        };
+        assert_eq!(
+            violations(&rules, &[75, 97, 47, 61, 53]),
+            vec![violation(97, 75, 1, 0)]
+        );
+        assert_eq!(
+            violations(&rules, &[97, 13, 75, 29, 47]),
+            vec![
+                violation(75, 13, 2, 1),
+                violation(29, 13, 3, 1),
+                violation(47, 13, 4, 1),
+                violation(47, 29, 4, 3),
+            ]
+        );
+
+        assert_eq!(
+            report(&rules, &[61, 13, 29]),
+            "update 61,13,29 breaks 1 rule:\n  29|13: 29 is at index 2, after 13 at index 1\n"
+        );
+        assert_eq!(
+            report(&rules, &[75, 29, 13]),
+            "update 75,29,13 is in the right order\n"
+        );
+        //Pages without rules can go anywhere
+        assert!(violations(&rules, &[13, 99, 12]).is_empty());
+    }
+
+    #[test_log::test]
+    fn day5_scoring() {
+        let even = [4, 3, 2, 1];
+        assert_eq!(Score::LowerMedian.score(&even), 3);
+        assert_eq!(Score::UpperMedian.score(&even), 2);
+        assert_eq!(Score::First.score(&even), 4);
+        assert_eq!(Score::Last.score(&even), 1);
+        assert_eq!(Score::LowerMedian.score(&[5, 6, 7]), 6);
+        assert_eq!(Score::default().score(&[]), 0);
+        assert_ne!(Score::Hash.score(&[1, 2]), Score::Hash.score(&[2, 1]));
+        assert_eq!(Score::Hash.score(&[]), 0);
+
+        let input = day5_parse(get_test_input()).unwrap();
+        assert_eq!(part1_with(&input, Score::First), 75 + 97 + 75);
+        assert_eq!(part2_with(&input, Score::Last).unwrap(), 53 + 13 + 13);
+
+        //Even updates don't have a middle, but they still get scored
+        let input = day5_parse("47|53\n97|47\n\n47,53\n53,97,47,13").unwrap();
+        assert_eq!(part1_with(&input, Score::LowerMedian), 47);
+        assert_eq!(part2_with(&input, Score::LowerMedian).unwrap(), 47);
+        assert_eq!(validate(&input.0, &input.1).even_updates, vec![0, 1]);
+    }
+
+    #[test_log::test]
+    fn day5_dot() {
+        let (rules, _) = day5_parse("47|53\n97|13\n97|47\n47|53\n\n47,97").unwrap();
+        assert_eq!(
+            to_dot(&rules, None),
+            "digraph rules {
+    47 -> 53;
+    97 -> 13;
+    97 -> 47;
+}
+"
+        );
+        assert_eq!(
+            to_dot(&rules, Some(&[53, 47, 97])),
+            "digraph rules {
+    53 [label=\"53 (0)\"];
+    47 [label=\"47 (1)\"];
+    97 [label=\"97 (2)\"];
+    47 -> 53 [color=red, penwidth=2];
+    97 -> 47 [color=red, penwidth=2];
+}
+"
+        );
+    }
+
+    #[test_log::test]
+    fn day5_validate() {
+        let (rules, updates) = day5_parse(get_test_input()).unwrap();
+        let validation = validate(&rules, &updates);
+        assert_eq!(validation, Validation::default());
+        assert_eq!(validation.to_string(), "rules and updates look fine");
+
+        let (rules, updates) =
+            day5_parse("1|2\n2|3\n3|4\n4|1\n3|1\n1|2\n\n1,2,4\n2,3,4,1\n5,1").unwrap();
+        let validation = validate(&rules, &updates);
+        assert_eq!(
+            validation,
+            Validation {
+                cycle: Some(vec![1, 2, 3]),
+                update_cycles: vec![(1, vec![1, 2, 3])],
+                duplicates: vec![(1, 2)],
+                unruled: vec![5],
+                even_updates: vec![1, 2],
+            }
+        );
+        assert!(!validation.is_ok());
+        assert_eq!(
+            validation.to_string(),
+            "rules have a cycle: 1 -> 2 -> 3 -> 1
+update 1 has a cycle: 1 -> 2 -> 3 -> 1
+duplicate rule 1|2
+no rules for page 5
+update 1 has an even number of pages
+update 2 has an even number of pages"
+        );
+
+        //Without 3 the only way round is the long way, which needs 1 and 4 together
+        let pages = BTreeSet::from([1, 2, 4]);
+        assert_eq!(shortest_cycle(&rules, Some(&pages)), None);
+        let pages = BTreeSet::from([1, 2, 3, 4]);
+        assert_eq!(shortest_cycle(&rules, Some(&pages)), Some(vec![1, 2, 3]));
+    }
+
+    #[test_log::test]
+    fn day5_parse_errors() {
+        let err = day5_parse("47|53\n97-13\n\n75,47").unwrap_err();
+        assert_eq!(
+            (err.line, err.column, err.snippet.as_str()),
+            (2, 1, "97-13")
+        );
+
+        let err = day5_parse("47|53\n\n75,4x,61").unwrap_err();
+        assert_eq!((err.line, err.column, err.snippet.as_str()), (3, 4, "4x"));
     }
 }
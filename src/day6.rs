@@ -1,4 +1,12 @@
+use crate::geometry::{Direction, Point};
+use crate::grid::Grid;
+use crate::parse::ParseError;
+use crate::render::{Layer, Render};
+use colored::Color;
+use log::{debug, log_enabled, trace, Level};
+use rayon::prelude::*;
 use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
 
 type InputType = Map;
 type OutputType = u64;
@@ -6,24 +14,152 @@ type OutputType = u64;
 #[derive(Clone)]
 pub struct Map {
     //I suspect part 2 will introduce multiple guards or sightlines, so let's keep this flexible
-    guard: ((i32, i32), GuardFacing),
-    initial_guard_position: ((i32, i32), GuardFacing),
-    visited: BTreeMap<(i32, i32),i32>, //May need to do this for each guard; for now, keep it simple
-    map: BTreeMap<(i32, i32), Location>,
-    max_x: i32,
-    max_y: i32,
+    guards: Vec<(Point, GuardFacing)>, //In the order they appear on the map
+    initial_guards: Vec<(Point, GuardFacing)>,
+    visited: Vec<BTreeSet<Point>>, //One for each guard
+    collisions: Collisions,
+    movement: Movement,
+    map: Grid<Location>,
+    walls: WallIndex,
+    obstacles: BTreeSet<Point>, //Walls put in by hand, drawn differently so they stand out
+    trajectory: Option<Trajectory>, //Only recorded when asked for
 }
 
-static LOOP_THRESHOLD: i32 = 10;
+/// What a guard does when its next step would land on another guard, or on the same square as
+/// another guard moving at the same time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Collisions {
+    /// Treat the other guard like any other obstacle and turn right
+    #[default]
+    Wall,
+    /// Guards don't notice each other
+    PassThrough,
+    /// Stand still for good, the guard becomes an obstacle for everyone else
+    Stop,
+}
+
+/// What a guard does when it runs into a wall
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Movement {
+    /// The puzzle's rule
+    #[default]
+    TurnRight,
+    TurnLeft,
+    TurnAround,
+    /// Right at the first wall, left at the next, and so on
+    Alternate,
+    /// Eight facings, turning 45 degrees right at each wall. Diagonal steps only care about the
+    /// square they land on, so a guard can slip between two walls touching at the corners
+    Diagonal,
+}
+
+impl Movement {
+    /// The new facing, and whether the guard's next turn is the other way (only for Alternate)
+    fn turn(self, facing: GuardFacing, other_way: bool) -> (GuardFacing, bool) {
+        match self {
+            Movement::TurnRight => (facing.turn_right(), false),
+            Movement::TurnLeft => (facing.turn_left(), false),
+            Movement::TurnAround => (facing.opposite(), false),
+            Movement::Alternate if other_way => (facing.turn_left(), false),
+            Movement::Alternate => (facing.turn_right(), true),
+            Movement::Diagonal => (facing.turn_right_45(), false),
+        }
+    }
+}
+
+/// How a simulation ended. A step is one move or one turn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// Walked off the map after `steps` steps
+    Exited { steps: usize },
+    /// The guard's position and facing after `cycle_start` steps comes round again every
+    /// `cycle_len` steps, forever
+    Looped { cycle_start: usize, cycle_len: usize },
+    /// Ran into another guard with `Collisions::Stop` after `steps` steps
+    Stopped { steps: usize },
+}
+
+impl Outcome {
+    pub fn is_loop(&self) -> bool {
+        matches!(self, Outcome::Looped { .. })
+    }
+}
+
+/// Where one guard was after one step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Step {
+    pub step: usize,
+    pub guard: usize,
+    pub position: Point,
+    pub facing: GuardFacing,
+    /// Turned this step instead of moving
+    pub turned: bool,
+}
+
+/// Every step of every guard, in order. Starts with where each guard was before moving (step 0)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trajectory {
+    pub steps: Vec<Step>,
+}
+
+impl Trajectory {
+    pub fn turns(&self) -> impl Iterator<Item = &Step> + '_ {
+        self.steps.iter().filter(|s| s.turned)
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut out = "step,guard,x,y,facing,turned\n".to_string();
+        for s in &self.steps {
+            writeln!(
+                out,
+                "{},{},{},{},{},{}",
+                s.step,
+                s.guard,
+                s.position.x,
+                s.position.y,
+                s.facing.abbreviation(),
+                s.turned
+            )
+            .unwrap();
+        }
+        out
+    }
+
+    /// A JSON array with one object per step, same fields as the CSV
+    pub fn to_json(&self) -> String {
+        let steps = self
+            .steps
+            .iter()
+            .map(|s| {
+                format!(
+                    concat!(
+                        "  {{\"step\": {}, \"guard\": {}, \"x\": {}, \"y\": {}, ",
+                        "\"facing\": \"{}\", \"turned\": {}}}"
+                    ),
+                    s.step,
+                    s.guard,
+                    s.position.x,
+                    s.position.y,
+                    s.facing.abbreviation(),
+                    s.turned
+                )
+            })
+            .collect::<Vec<_>>();
+        format!("[\n{}\n]\n", steps.join(",\n"))
+    }
+}
 
-#[derive(Clone, Copy, PartialEq)]
-pub enum GuardFacing {
-    North,
-    East,
-    South,
-    West,
+/// Somewhere an extra obstacle traps the guard, and the loop it ends up in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoopObstacle {
+    pub position: Point,
+    /// Steps before the guard is on the loop for good
+    pub cycle_start: usize,
+    pub cycle_len: usize,
 }
 
+pub type GuardFacing = Direction;
+
 #[derive(Clone, Copy)]
 pub enum Location {
     Empty,
@@ -31,182 +167,516 @@ pub enum Location {
     Guard(GuardFacing),
 }
 
-impl GuardFacing {
-    fn from_char(c: char) -> GuardFacing {
+impl Location {
+    fn into_char(self) -> char {
+        match self {
+            Location::Empty => '.',
+            Location::Wall => '#',
+            Location::Guard(facing) => facing.into_char(),
+        }
+    }
+
+    fn from_char(c: char) -> Result<Location, String> {
         match c {
-            '^' => GuardFacing::North,
-            '>' => GuardFacing::East,
-            'v' => GuardFacing::South,
-            '<' => GuardFacing::West,
-            _ => panic!("Invalid character for guard facing"),
+            '.' => Ok(Location::Empty),
+            '#' => Ok(Location::Wall),
+            c => GuardFacing::from_char(c)
+                .map(Location::Guard)
+                .ok_or_else(|| format!("Invalid character in map '{}'", c)),
         }
     }
+}
 
-    fn turn_right(&self) -> GuardFacing {
-        match self {
-            GuardFacing::North => GuardFacing::East,
-            GuardFacing::East => GuardFacing::South,
-            GuardFacing::South => GuardFacing::West,
-            GuardFacing::West => GuardFacing::North,
+/// Where the walls are in each row and column, sorted, so the guard can jump straight to the next
+/// one instead of walking there a cell at a time
+#[derive(Clone)]
+struct WallIndex {
+    rows: Vec<Vec<i64>>,    //x of each wall, by y
+    columns: Vec<Vec<i64>>, //y of each wall, by x
+}
+
+impl WallIndex {
+    fn new(map: &Grid<Location>) -> WallIndex {
+        let mut walls = WallIndex {
+            rows: vec![Vec::new(); map.height()],
+            columns: vec![Vec::new(); map.width()],
+        };
+        for (p, loc) in map.iter() {
+            if let Location::Wall = loc {
+                walls.insert(p);
+            }
         }
+        walls
     }
 
-    fn get_delta_step(&self) -> (i32, i32) {
-        match self {
-            GuardFacing::North => (0, -1),
-            GuardFacing::East => (1, 0),
-            GuardFacing::South => (0, 1),
-            GuardFacing::West => (-1, 0),
+    fn insert(&mut self, p: Point) {
+        fn insert_sorted(line: &mut Vec<i64>, v: i64) {
+            if let Err(i) = line.binary_search(&v) {
+                line.insert(i, v);
+            }
         }
+        insert_sorted(&mut self.rows[p.y as usize], p.x);
+        insert_sorted(&mut self.columns[p.x as usize], p.y);
     }
 
-    #[allow(dead_code)]
-    fn into_char(self) -> char {
-        match self {
-            GuardFacing::North => '^',
-            GuardFacing::East => '>',
-            GuardFacing::South => 'v',
-            GuardFacing::West => '<',
+    /// The first wall the guard would run into walking from `from`, None if it walks off the map.
+    /// `extra` is one more wall on top of the index, so it can be shared while trying obstacles.
+    fn next_wall(&self, from: Point, facing: GuardFacing, extra: Option<Point>) -> Option<Point> {
+        let (line, pos) = match facing {
+            Direction::North | Direction::South => (&self.columns[from.x as usize], from.y),
+            Direction::East | Direction::West => (&self.rows[from.y as usize], from.x),
+            _ => panic!("The guard only walks in straight lines"),
+        };
+        let found = match facing {
+            Direction::North | Direction::West => {
+                line[..line.partition_point(|&w| w < pos)].last()
+            }
+            _ => line.get(line.partition_point(|&w| w <= pos)),
+        };
+        let found = found.map(|&w| match facing {
+            Direction::North | Direction::South => Point::new(from.x, w),
+            _ => Point::new(w, from.y),
+        });
+
+        //Is the extra wall straight ahead, and nearer than anything in the index?
+        let ahead = |p: Point| {
+            let d = p - from;
+            d.manhattan() > 0 && facing.vector() * d.manhattan() == d
+        };
+        match (found, extra.filter(|&e| ahead(e))) {
+            (Some(w), Some(e)) if (w - from).manhattan() < (e - from).manhattan() => Some(w),
+            (found, None) => found,
+            (_, extra) => extra,
         }
     }
 }
 
-impl Location {
-    fn from_char(c: char) -> Location {
-        match c {
-            '.' => Location::Empty,
-            '#' => Location::Wall,
-            '^' | '>' | 'v' | '<' => Location::Guard(GuardFacing::from_char(c)),
-            c => panic!("Invalid character in map '{}'",c),
-        }
+/// Scratch space for jump_until_exit, each rayon worker keeps one and reuses it between obstacles
+#[derive(Default)]
+struct Probe {
+    //Step each straight run starts at, where, which way, and which way the next turn goes
+    runs: Vec<(usize, Point, GuardFacing, bool)>,
+    //Only states right after a turn are recorded, so a loop is spotted at the first repeated
+    //turn. Every loop has at least one turn in it, and one of each per lap
+    seen: BTreeMap<(Point, GuardFacing, bool), usize>,
+}
+
+impl Probe {
+    //Where the guard was after `step` steps, going by the straight runs
+    fn state_at(&self, step: usize) -> (Point, GuardFacing, bool) {
+        let run = self.runs[self.runs.partition_point(|r| r.0 <= step) - 1];
+        let (start, from, facing, other_way) = run;
+        (from + facing.vector() * (step - start) as i64, facing, other_way)
     }
 }
 
 #[aoc_generator(day6)]
-fn day6_parse(input: &str) -> InputType {
-    input.lines().enumerate().fold(
-        Map {
-            guard: ((-100, -100), GuardFacing::North),
-            initial_guard_position: ((-100, -100), GuardFacing::North),
-            visited: BTreeMap::new(),
-            map: BTreeMap::new(),
-            max_x: 0,
-            max_y: 0,
-        },
-        |mut map, (y, line)| {
-            line.chars().enumerate().for_each(|(x, c)| {
-                if x as i32 > map.max_x {
-                    map.max_x = x as i32;
-                }
-                if y as i32 > map.max_y {
-                    map.max_y = y as i32;
-                }
-                let loc = Location::from_char(c);
-                if let Location::Guard(g) = loc {
-                    map.guard = ((x as i32, y as i32), g);
-                    map.initial_guard_position= ((x as i32, y as i32), g);
-                    map.visited.insert((x as i32, y as i32),1);
-                } else if let Location::Wall = loc {
-                    map.map.insert((x as i32, y as i32), loc);
-                } else {
-                    //Do nothing, we don't need empty locations
-                }
-            });
-            map
-        },
-        )
+pub fn day6_parse(input: &str) -> Result<InputType, ParseError> {
+    let mut map = Grid::try_parse(input, Location::from_char)?;
+
+    let guards = map
+        .iter()
+        .filter_map(|(p, loc)| match loc {
+            Location::Guard(g) => Some((p, *g)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if guards.is_empty() {
+        return Err(ParseError::missing(input, input, "a guard (^, >, v or <) on the map"));
+    }
+    //Guards move, so the map itself only needs to know they started on empty squares
+    for (p, _) in &guards {
+        map.set(*p, Location::Empty);
+    }
+
+    Ok(Map {
+        initial_guards: guards.clone(),
+        visited: guards.iter().map(|(p, _)| BTreeSet::from([*p])).collect(),
+        guards,
+        collisions: Collisions::default(),
+        movement: Movement::default(),
+        walls: WallIndex::new(&map),
+        map,
+        obstacles: BTreeSet::new(),
+        trajectory: None,
+    })
 }
 
 impl Map {
-    #[allow(dead_code)]
-    fn dump_map(&self, corner_x: i32, corner_y: i32, obstacle_x: i32, obstacle_y: i32) {
-        for y in 0..=self.max_y {
-            for x in 0..=self.max_x {
-                if x == corner_x && y == corner_y {
-                    print!("X");
-                    continue;
-                }
+    pub fn with_collisions(mut self, collisions: Collisions) -> Map {
+        self.collisions = collisions;
+        self
+    }
 
-                if x == obstacle_x && y == obstacle_y {
-                    print!("O");
-                    continue;
-                }
-                if let Some(count) = self.visited.get(&(x, y)) {
-                    print!("{}", count);
-                    continue;
-                }
-                let loc = self.map.get(&(x, y)).unwrap_or(&Location::Empty);
-                match loc {
-                    Location::Empty => print!("."),
-                    Location::Wall => print!("#"),
-                    Location::Guard(facing) => print!("{}", facing.into_char()),
-                }
+    pub fn with_movement(mut self, movement: Movement) -> Map {
+        self.movement = movement;
+        self
+    }
+
+    /// Put a wall at `obstacle`, to see what it does to the guards
+    pub fn with_obstacle(mut self, obstacle: Point) -> Result<Map, String> {
+        if !self.map.in_bounds(obstacle) {
+            return Err(format!("obstacle {} is off the map", obstacle));
+        }
+        if self.initial_guards.iter().any(|(p, _)| *p == obstacle) {
+            return Err(format!("obstacle {} is where a guard starts", obstacle));
+        }
+        self.map.set(obstacle, Location::Wall);
+        self.walls.insert(obstacle);
+        self.obstacles.insert(obstacle);
+        Ok(self)
+    }
+
+    /// Record every step of the next simulation, see `trajectory`
+    pub fn recording(mut self) -> Map {
+        self.trajectory = Some(Trajectory::default());
+        self
+    }
+
+    pub fn trajectory(&self) -> Option<&Trajectory> {
+        self.trajectory.as_ref()
+    }
+
+    /// The map after each step of a recorded trajectory, with the path so far and the guards that
+    /// are still walking. Each frame is only drawn when it's asked for
+    pub fn frames<'a>(
+        &'a self,
+        trajectory: &'a Trajectory,
+        colour: bool,
+    ) -> impl Iterator<Item = String> + 'a {
+        //The path only ever grows, so it's kept from frame to frame and the guards go on top
+        let mut render = Render::new(&self.map)
+            .colour(colour)
+            .layer(Layer::new([]).colour(Color::Yellow).fallback('+'))
+            .layer(Layer::new(self.obstacles.iter().copied()).glyph('O').colour(Color::Red));
+        let mut steps = trajectory.steps.iter().peekable();
+
+        std::iter::from_fn(move || {
+            let layers = render.layers_mut();
+            layers.truncate(2);
+            //Draw once all the guards have taken this step
+            let first = steps.next()?;
+            let mut next = Some(first);
+            while let Some(step) = next {
+                layers[0].insert(step.position);
+                let guard = Layer::new([step.position]).glyph(step.facing.into_char());
+                layers.push(guard.colour(Color::Green));
+                next = steps.next_if(|next| next.step == first.step);
             }
-            println!();
+            Some(render.render(|loc| loc.into_char()))
+        })
+    }
+
+    #[allow(dead_code)]
+    fn dump_map(&self, corner: Point, obstacle: Point) {
+        //Rendering the whole map every step is expensive, don't bother unless someone's looking
+        if !log_enabled!(Level::Trace) {
+            return;
         }
+        let visited = self.visited.iter().flatten().copied();
+        let render = Render::new(&self.map)
+            .layer(Layer::new(visited).colour(Color::Yellow).fallback('+'));
+        let render = self.guards.iter().fold(render, |render, &(guard, facing)| {
+            render.layer(Layer::new([guard]).glyph(facing.into_char()).colour(Color::Green))
+        });
+        let render = render
+            .layer(Layer::new([corner]).glyph('X').colour(Color::Cyan))
+            .layer(Layer::new([obstacle]).glyph('O').colour(Color::Red));
+        trace!("\n{}", render.render(|loc| loc.into_char()));
     }
 
-    fn find_infinite_loop(&self, obstacle: (i32,i32)) -> bool {
+    fn try_obstacle(&self, obstacle: Point, probe: &mut Probe) -> Outcome {
 
         //I don't know why my original code works for the test case, but not my input, instead of trying to figure out why, just brute force this.
 
-        //Inject the obstacle into the map, run the simulation, and see if we end up in a loop
+        //Run the guard from the start with our fake obstacle in the way, and see if we end up in
+        //a loop. The obstacle only lives in the probe, so the map is shared, not cloned
+        debug!("trying obstacle at {}", obstacle);
+        self.dump_map(Point::new(-100, -100), obstacle);
+
+        //The wall index only knows about the four straight facings, diagonal guards have to walk
+        //it a step at a time
+        if self.movement == Movement::Diagonal {
+            return self.walk_until_exit(self.initial_guards[0], obstacle, probe);
+        }
+
+        self.jump_until_exit(self.initial_guards[0], Some(obstacle), probe)
+    }
+
+    /// Every square an obstacle could go to trap the first guard in a loop, in position order.
+    /// The puzzle only has the one guard, if there's more only the first guard's route is tried,
+    /// on its own
+    pub fn loop_obstacles(&self) -> Vec<LoopObstacle> {
         let mut map = self.clone();
-        //Inject our fake obstacle
-        map.map.insert(obstacle, Location::Wall);
-        map.guard = map.initial_guard_position; //Reset
+        let start = map.initial_guards[0];
+        map.guards = vec![start]; //Reset, and leave the other guards out of it
+        map.visited = vec![BTreeSet::from([start.0])];
+        map.trajectory = None;
+
+        //The obstacle has to be somewhere the guard would have walked, so do part one first
+        map.simulate_until_exit();
 
-        #[cfg(test)]
-        map.dump_map(-100, -100, obstacle.0, obstacle.1);
+        //Find all the visited locations
+        let mut visited_locations = map.visited[0].clone();
+        visited_locations.remove(&start.0); //Except the initial position
+
+        //Each candidate is independent, so spread them over the thread pool. Collecting keeps
+        //the order of the set, however the work gets split up
+        visited_locations
+            .into_par_iter()
+            .map_init(Probe::default, |probe, p| (p, map.try_obstacle(p, probe)))
+            .filter_map(|(position, outcome)| match outcome {
+                Outcome::Looped {
+                    cycle_start,
+                    cycle_len,
+                } => Some(LoopObstacle {
+                    position,
+                    cycle_start,
+                    cycle_len,
+                }),
+                _ => None,
+            })
+            .collect()
+    }
 
-        map.simulate_until_exit()
+    /// The map with each of `obstacles` drawn as an 'O', and where the guards start
+    pub fn render_obstacles(&self, obstacles: &[LoopObstacle], colour: bool) -> String {
+        let render = Render::new(&self.map).colour(colour).layer(
+            Layer::new(obstacles.iter().map(|o| o.position)).glyph('O').colour(Color::Red),
+        );
+        let render = self.initial_guards.iter().fold(render, |render, &(guard, facing)| {
+            render.layer(Layer::new([guard]).glyph(facing.into_char()).colour(Color::Green))
+        });
+        render.render(|loc| loc.into_char())
     }
 
-    //Return true if we got stuck in a loop
-    fn simulate_until_exit(&mut self) -> bool {
+    //Same answer as simulate_until_exit for one guard on its own, with `obstacle` as an extra
+    //wall. Cell by cell like simulate_until_exit, but doesn't need its own copy of the map
+    fn walk_until_exit(
+        &self,
+        start: (Point, GuardFacing),
+        obstacle: Point,
+        probe: &mut Probe,
+    ) -> Outcome {
+        let (mut guard, mut facing) = start;
+        let mut other_way = false;
+        let mut step = 0;
+        //Every state is recorded, so the first repeat is exactly where the loop starts
+        probe.seen.clear();
+        probe.seen.insert((guard, facing, other_way), step);
+
         loop {
-            //Do a step
-            let ((guard_x, guard_y), facing) = self.guard;
-
-            //First, what's in front of the guard?
-            //NOTE: coordinates are inverted for y
-            let (dx, dy) = facing.get_delta_step();
-
-            let (next_x, next_y) = (guard_x + dx, guard_y + dy);
-            match self.map.get(&(next_x, next_y)) {
-                Some(Location::Wall) => {
-                    //Turn right
-                    self.guard = ((guard_x, guard_y), facing.turn_right());
-                    //self.turn_locations.insert((guard_x, guard_y));
-                    *self.visited.entry((guard_x, guard_y)).or_insert(0) += 1;
+            step += 1;
+            let next = guard.step(facing);
+            match self.map.get(next) {
+                None => return Outcome::Exited { steps: step - 1 },
+                Some(Location::Wall) => (facing, other_way) = self.movement.turn(facing, other_way),
+                Some(_) if next == obstacle => {
+                    (facing, other_way) = self.movement.turn(facing, other_way)
                 }
-                Some(Location::Empty) => {
-                    //Move forward
-                    panic!("We don't have explicit empties in here");
+                Some(_) => guard = next,
+            }
+            if let Some(first) = probe.seen.insert((guard, facing, other_way), step) {
+                return Outcome::Looped {
+                    cycle_start: first,
+                    cycle_len: step - first,
+                };
+            }
+        }
+    }
+
+    //Same answer as simulate_until_exit, but goes wall to wall instead of cell by cell. Doesn't
+    //track visited, so it's no good for part 1, and only walks in straight lines
+    fn jump_until_exit(
+        &self,
+        start: (Point, GuardFacing),
+        obstacle: Option<Point>,
+        probe: &mut Probe,
+    ) -> Outcome {
+        let (mut guard, mut facing) = start;
+        let mut other_way = false;
+        let mut step = 0;
+        probe.runs.clear();
+        probe.seen.clear();
+
+        loop {
+            if let Some(&first) = probe.seen.get(&(guard, facing, other_way)) {
+                let cycle_len = step - first;
+                //The loop may have started mid-run before that turn, find the first step that
+                //lines up with the one a lap later
+                let (mut lo, mut hi) = (0, first);
+                while lo < hi {
+                    let mid = (lo + hi) / 2;
+                    if probe.state_at(mid) == probe.state_at(mid + cycle_len) {
+                        hi = mid;
+                    } else {
+                        lo = mid + 1;
+                    }
                 }
-                Some(Location::Guard(_)) => {
-                    panic!("Guards can't run into each other");
+                let cycle_start = lo;
+                return Outcome::Looped {
+                    cycle_start,
+                    cycle_len,
+                };
+            }
+            if !probe.runs.is_empty() {
+                probe.seen.insert((guard, facing, other_way), step);
+            }
+            probe.runs.push((step, guard, facing, other_way));
+
+            match self.walls.next_wall(guard, facing, obstacle) {
+                Some(wall) => {
+                    let stop = wall - facing.vector();
+                    //Every move up to the wall, then the turn
+                    step += (stop - guard).manhattan() as usize + 1;
+                    guard = stop;
+                    (facing, other_way) = self.movement.turn(facing, other_way);
                 }
                 None => {
-                    // There is nothing here, BUT, we can't move off the map
-                    if next_x < 0 || next_x > self.max_x || next_y < 0 || next_y > self.max_y {
-                        //Guard walked out of bounds
-                        return false; //Not a loop
-                    } else {
-                        //Move forward
-                        self.guard = ((next_x, next_y), facing);
-                        *self.visited.entry((next_x, next_y)).or_insert(0) += 1;
-                        let visits = self.visited.get(&(next_x, next_y)).unwrap();
-                        #[cfg(test)]
-                        self.dump_map(guard_x, guard_y, -100, -100);
-                        if *visits > LOOP_THRESHOLD {
-                            return true;
-                        }
+                    let to_edge = match facing {
+                        Direction::North => guard.y,
+                        Direction::South => self.map.max_y() - guard.y,
+                        Direction::East => self.map.max_x() - guard.x,
+                        _ => guard.x,
+                    };
+                    return Outcome::Exited {
+                        steps: step + to_edge as usize,
+                    };
+                }
+            }
+        }
+    }
+
+    //Every guard steps at the same time, deciding what to do from where everyone was before the
+    //step. The guards are deterministic, so the first time they're all back in (position,
+    //facing)s they've all been in together, they're going round in circles. One outcome per guard
+    pub fn simulate_until_exit(&mut self) -> Vec<Outcome> {
+        let mut outcomes: Vec<Option<Outcome>> = vec![None; self.guards.len()];
+        //Which way each guard turns next matters too when they alternate
+        let mut other_way = vec![false; self.guards.len()];
+        //Each guard's (position, facing) after every step, for working out its own loop
+        let mut history = self.guards.iter().map(|&g| vec![(g, false)]).collect::<Vec<_>>();
+        let state = (self.guards.iter().enumerate()).map(|(g, &guard)| (g, guard, false));
+        let mut seen = BTreeMap::from([(state.collect(), 0)]);
+        if let Some(trajectory) = &mut self.trajectory {
+            trajectory.steps = (self.guards.iter().enumerate())
+                .map(|(guard, &(position, facing))| Step {
+                    step: 0,
+                    guard,
+                    position,
+                    facing,
+                    turned: false,
+                })
+                .collect();
+        }
 
+        for step in 1.. {
+            //Squares guards are in (exited guards are gone) or heading for. More than one guard
+            //heading for a square blocks all of them
+            let mut occupied = BTreeMap::new();
+            for (g, (guard, facing)) in self.guards.iter().enumerate() {
+                match outcomes[g] {
+                    Some(Outcome::Exited { .. }) => {}
+                    Some(_) => *occupied.entry(*guard).or_insert(0) += 2,
+                    None => {
+                        *occupied.entry(*guard).or_insert(0) += 2;
+                        *occupied.entry(guard.step(*facing)).or_insert(0) += 1;
                     }
                 }
             }
+
+            for g in 0..self.guards.len() {
+                if outcomes[g].is_some() {
+                    continue;
+                }
+                //Do a step
+                let (guard, facing) = self.guards[g];
+
+                //First, what's in front of the guard?
+                //NOTE: coordinates are inverted for y
+                let next = guard.step(facing);
+                let blocked = self.collisions != Collisions::PassThrough && occupied[&next] > 1;
+                let mut turn = || {
+                    let (turned_to, next_other_way) = self.movement.turn(facing, other_way[g]);
+                    other_way[g] = next_other_way;
+                    (guard, turned_to)
+                };
+                match self.map.get(next) {
+                    Some(Location::Wall) => {
+                        //Turn
+                        self.guards[g] = turn();
+                    }
+                    Some(Location::Empty) if blocked => {
+                        if self.collisions == Collisions::Stop {
+                            outcomes[g] = Some(Outcome::Stopped { steps: step - 1 });
+                        } else {
+                            self.guards[g] = turn();
+                        }
+                    }
+                    Some(Location::Empty) => {
+                        //Move forward
+                        self.guards[g] = (next, facing);
+                        self.visited[g].insert(next);
+                        self.dump_map(guard, Point::new(-100, -100));
+                    }
+                    Some(Location::Guard(_)) => {
+                        unreachable!("Guards are taken off the map when it's parsed");
+                    }
+                    None => {
+                        //Guard walked out of bounds
+                        outcomes[g] = Some(Outcome::Exited { steps: step - 1 });
+                    }
+                }
+                history[g].push((self.guards[g], other_way[g]));
+                if let (Some(trajectory), None) = (&mut self.trajectory, outcomes[g]) {
+                    let (position, turned_to) = self.guards[g];
+                    trajectory.steps.push(Step {
+                        step,
+                        guard: g,
+                        position,
+                        facing: turned_to,
+                        turned: turned_to != facing,
+                    });
+                }
+            }
+
+            let walking = (0..self.guards.len()).filter(|&g| outcomes[g].is_none());
+            let walking = walking.collect::<Vec<_>>();
+            if walking.is_empty() {
+                break;
+            }
+            //Exited guards can't come back, stopped ones can't move again, so only the guards still
+            //walking make up the state
+            let state = walking.iter().map(|&g| (g, self.guards[g], other_way[g]));
+            let state = state.collect::<Vec<_>>();
+            if let Some(first) = seen.insert(state, step) {
+                for g in walking {
+                    outcomes[g] = Some(Map::cycle_of(&history[g], first, step));
+                }
+                break;
+            }
         } //end match
+
+        outcomes.into_iter().map(|o| o.unwrap()).collect()
+    }
+
+    //All the guards together repeat every `step - first` steps, but one guard on its own might go
+    //round faster, or have joined its loop earlier than the others
+    fn cycle_of<T: PartialEq>(history: &[T], first: usize, step: usize) -> Outcome {
+        let lap = step - first;
+        let repeats = |t: usize, len: usize| history[t] == history[t + len];
+        let cycle_len = (1..=lap)
+            .filter(|&len| lap.is_multiple_of(len))
+            .find(|&len| (first..=step - len).all(|t| repeats(t, len)))
+            .unwrap();
+        let mut cycle_start = first;
+        while cycle_start > 0 && repeats(cycle_start - 1, cycle_len) {
+            cycle_start -= 1;
+        }
+        Outcome::Looped {
+            cycle_start,
+            cycle_len,
+        }
     }
 }
 
@@ -220,21 +690,13 @@ impl Map {
 
         map.simulate_until_exit();
 
-        map.visited.len() as u64
+        //Any square any guard stood on
+        map.visited.iter().flatten().collect::<BTreeSet<_>>().len() as u64
     }
 
 #[aoc(day6, part2)]
     pub fn part2(input: &InputType) -> OutputType {
-        let mut map = input.clone();
-
-        //First, do part one,
-        map.simulate_until_exit();
-
-        //Find all the visited locations
-        let mut visited_locations = map.visited.keys().cloned().collect::<BTreeSet<(i32, i32)>>();
-        visited_locations.remove(&map.initial_guard_position.0); //Except the initial position
-
-        visited_locations.iter().filter(|(x,y)| map.find_infinite_loop((*x,*y))).count() as u64
+        input.loop_obstacles().len() as u64
 
     }
 
@@ -256,12 +718,280 @@ impl Map {
 ......#..."
         }
 
-        #[test]
+        #[test_log::test]
         fn day6_part1() {
-            assert_eq!(part1(&day6_parse(get_test_input())), 41);
+            assert_eq!(part1(&day6_parse(get_test_input()).unwrap()), 41);
         }
-        #[test]
+        #[test_log::test]
         fn day6_part2() {
-            assert_eq!(part2(&day6_parse(get_test_input())), 6);
+            assert_eq!(part2(&day6_parse(get_test_input()).unwrap()), 6);
+        }
+
+        #[test_log::test]
+        fn day6_part2_any_thread_count() {
+            let map = day6_parse(get_test_input()).unwrap();
+            for threads in [1, 3] {
+                let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+                assert_eq!(pool.install(|| part2(&map)), 6);
+            }
+        }
+
+        #[test_log::test]
+        fn day6_loop_obstacles() {
+            let map = day6_parse(get_test_input()).unwrap();
+            let obstacles = map.loop_obstacles();
+
+            //The six from the puzzle text
+            let positions = obstacles.iter().map(|o| (o.position.x, o.position.y));
+            assert_eq!(
+                positions.collect::<Vec<_>>(),
+                [(1, 8), (3, 6), (3, 8), (6, 7), (7, 7), (7, 9)]
+            );
+            //Blocking the guard's first turn, it goes round from the very start
+            assert_eq!(
+                obstacles[1],
+                LoopObstacle { position: Point::new(3, 6), cycle_start: 0, cycle_len: 22 }
+            );
+
+            assert_eq!(
+                map.render_obstacles(&obstacles, false),
+                "....#.....
+.........#
+..........
+..#.......
+.......#..
+..........
+.#.O^.....
+......OO#.
+#O.O......
+......#O..
+"
+            );
+        }
+
+        #[test_log::test]
+        fn day6_loop_obstacles_multiple_guards() {
+            //The second guard walks into the first guard's route, which mustn't hide any of the
+            //squares the first guard walks on its own
+            let map = day6_parse(".#...\n...#.\n#>..#\n.#<##\n.##..").unwrap();
+            let obstacles = map.loop_obstacles();
+            let positions = obstacles.iter().map(|o| (o.position.x, o.position.y));
+            assert_eq!(positions.collect::<Vec<_>>(), [(1, 1), (2, 0), (2, 1), (2, 3)]);
+
+            let alone = day6_parse(".#...\n...#.\n#>..#\n.#.##\n.##..").unwrap();
+            assert_eq!(alone.loop_obstacles(), obstacles);
+        }
+
+        #[test_log::test]
+        fn day6_outcome() {
+            let mut map = day6_parse(get_test_input()).unwrap();
+            assert_eq!(map.simulate_until_exit(), [Outcome::Exited { steps: 54 }]);
+
+            //Boxed in on all four sides, it just spins on the spot
+            let mut map = day6_parse(".#.\n#^#\n.#.").unwrap();
+            assert_eq!(
+                map.simulate_until_exit(),
+                [Outcome::Looped { cycle_start: 0, cycle_len: 4 }]
+            );
+
+            //Walks up to the loop first
+            let mut map = day6_parse(".#...\n....#\n.....\n#....\n...#.\n.^...").unwrap();
+            let outcome = map.simulate_until_exit();
+            assert_eq!(outcome, [Outcome::Looped { cycle_start: 2, cycle_len: 12 }]);
+        }
+
+        #[test_log::test]
+        fn day6_multiple_guards() {
+            let simulate = |input: &str, collisions| {
+                let mut map = day6_parse(input).unwrap().with_collisions(collisions);
+                (map.simulate_until_exit(), map.visited)
+            };
+            let exited = |steps| Outcome::Exited { steps };
+            let stopped = |steps| Outcome::Stopped { steps };
+
+            //Walk towards each other, bump, and turn off the map
+            let (outcomes, visited) = simulate(">..<", Collisions::Wall);
+            assert_eq!(outcomes, [exited(2), exited(2)]);
+            let xs = |g: usize| visited[g].iter().map(|p| p.x).collect::<Vec<_>>();
+            assert_eq!((xs(0), xs(1)), (vec![0, 1], vec![2, 3]));
+
+            let (outcomes, _) = simulate(">..<", Collisions::PassThrough);
+            assert_eq!(outcomes, [exited(3), exited(3)]);
+            let (outcomes, _) = simulate(">..<", Collisions::Stop);
+            assert_eq!(outcomes, [stopped(1), stopped(1)]);
+
+            //Both heading for the same square
+            let (outcomes, _) = simulate(">.<", Collisions::Stop);
+            assert_eq!(outcomes, [stopped(0), stopped(0)]);
+
+            //A stopped guard is in the way of the one that's left
+            let (outcomes, _) = simulate("v..\n...\n^.<", Collisions::Stop);
+            assert_eq!(outcomes, [stopped(0), stopped(0), stopped(1)]);
+
+            //One guard spins on the spot while the other walks off, then keeps spinning alone
+            let (outcomes, _) = simulate(".#.....\n#^#..>.\n.#.....", Collisions::Wall);
+            let spinning = Outcome::Looped { cycle_start: 0, cycle_len: 4 };
+            assert_eq!(outcomes, [spinning, exited(1)]);
+        }
+
+        #[test_log::test]
+        fn day6_movement() {
+            let simulate = |input: &str, movement| {
+                let mut map = day6_parse(input).unwrap().with_movement(movement);
+                (map.simulate_until_exit()[0], map.visited.remove(0))
+            };
+            let spinning = |cycle_len| Outcome::Looped { cycle_start: 0, cycle_len };
+
+            let boxed_in = ".#.\n#^#\n.#.";
+            assert_eq!(simulate(boxed_in, Movement::TurnLeft).0, spinning(4));
+            assert_eq!(simulate(boxed_in, Movement::TurnAround).0, spinning(2));
+            //Right to face east, left back to north, and round again
+            assert_eq!(simulate(boxed_in, Movement::Alternate).0, spinning(2));
+            //Gets out through the corner
+            let (outcome, visited) = simulate(boxed_in, Movement::Diagonal);
+            assert_eq!(outcome, Outcome::Exited { steps: 2 });
+            assert!(visited.contains(&Point::new(2, 0)));
+
+            let (outcome, _) = simulate(".#.\n...\n.^.", Movement::TurnAround);
+            assert_eq!(outcome, Outcome::Exited { steps: 3 });
+
+            //Right at the first wall, left at the second
+            let (outcome, visited) = simulate("#....\n...#.\n^....", Movement::Alternate);
+            assert_eq!(outcome, Outcome::Exited { steps: 6 });
+            assert!(visited.contains(&Point::new(2, 0)));
+            let (_, visited) = simulate("#....\n...#.\n^....", Movement::TurnRight);
+            assert!(visited.contains(&Point::new(2, 2)));
+
+            //Turning left in a mirror image is the same walk, and part 2 has to get there without
+            //the jump table
+            let mirrored = get_test_input()
+                .lines()
+                .map(|l| l.chars().rev().collect::<String>())
+                .collect::<Vec<_>>()
+                .join("\n");
+            let map = day6_parse(&mirrored).unwrap().with_movement(Movement::TurnLeft);
+            assert_eq!((part1(&map), part2(&map)), (41, 6));
+        }
+
+        #[test_log::test]
+        fn day6_trajectory() {
+            let map = day6_parse("...\n^.#\n...").unwrap();
+            assert!(map.clone().with_obstacle(Point::new(0, 1)).is_err());
+            assert!(map.clone().with_obstacle(Point::new(3, 0)).is_err());
+            let mut map = map.with_obstacle(Point::new(0, 0)).unwrap().recording();
+            assert_eq!(map.simulate_until_exit(), [Outcome::Exited { steps: 4 }]);
+
+            let trajectory = map.trajectory().unwrap();
+            assert_eq!(
+                trajectory.to_csv(),
+                "step,guard,x,y,facing,turned
+0,0,0,1,N,false
+1,0,0,1,E,true
+2,0,1,1,E,false
+3,0,1,1,S,true
+4,0,1,2,S,false
+"
+            );
+            assert_eq!(
+                trajectory.to_json(),
+                r#"[
+  {"step": 0, "guard": 0, "x": 0, "y": 1, "facing": "N", "turned": false},
+  {"step": 1, "guard": 0, "x": 0, "y": 1, "facing": "E", "turned": true},
+  {"step": 2, "guard": 0, "x": 1, "y": 1, "facing": "E", "turned": false},
+  {"step": 3, "guard": 0, "x": 1, "y": 1, "facing": "S", "turned": true},
+  {"step": 4, "guard": 0, "x": 1, "y": 2, "facing": "S", "turned": false}
+]
+"#
+            );
+            //Diagonal facings get their own names too
+            let diagonal = day6_parse(".#.\n#^#\n.#.").unwrap().with_movement(Movement::Diagonal);
+            let mut diagonal = diagonal.recording();
+            diagonal.simulate_until_exit();
+            assert_eq!(
+                diagonal.trajectory().unwrap().to_csv(),
+                "step,guard,x,y,facing,turned
+0,0,1,1,N,false
+1,0,1,1,NE,true
+2,0,2,0,NE,false
+"
+            );
+
+            let turns = trajectory.turns().map(|s| s.step).collect::<Vec<_>>();
+            assert_eq!(turns, [1, 3]);
+
+            let frames = map.frames(trajectory, false).collect::<Vec<_>>();
+            assert_eq!(frames.len(), 5);
+            assert_eq!(frames[2], "O..\n+>#\n...\n");
+            assert_eq!(frames[4], "O..\n++#\n.v.\n");
+
+            //One frame per step, with every guard in it
+            let mut map = day6_parse(">..<").unwrap().recording();
+            map.simulate_until_exit();
+            let frames = map.frames(map.trajectory().unwrap(), false).collect::<Vec<_>>();
+            assert_eq!(frames, [">..<\n", "+><+\n", "+v^+\n"]);
+        }
+
+        #[test_log::test]
+        fn day6_jump_matches_steps() {
+            //Try an obstacle everywhere, the two simulations have to agree on every outcome
+            let movements = [
+                Movement::TurnRight,
+                Movement::TurnLeft,
+                Movement::TurnAround,
+                Movement::Alternate,
+            ];
+            for movement in movements {
+                let map = day6_parse(get_test_input()).unwrap().with_movement(movement);
+                jump_matches_steps(&map);
+            }
+        }
+
+        fn jump_matches_steps(map: &Map) {
+            for (p, loc) in map.map.iter() {
+                let start = map.initial_guards[0];
+                if !matches!(loc, Location::Empty) || p == start.0 {
+                    continue;
+                }
+                let jumped = map.jump_until_exit(start, Some(p), &mut Probe::default());
+
+                let mut with_obstacle = map.clone();
+                with_obstacle.map.set(p, Location::Wall);
+                with_obstacle.walls.insert(p);
+                let probe = &mut Probe::default();
+                assert_eq!(jumped, with_obstacle.jump_until_exit(start, None, probe));
+                let stepped = with_obstacle.simulate_until_exit();
+                assert_eq!([jumped], *stepped, "obstacle at {} with {:?}", p, map.movement);
+                let walked = map.walk_until_exit(start, p, &mut Probe::default());
+                assert_eq!(walked, jumped, "obstacle at {} with {:?}", p, map.movement);
+            }
+        }
+
+        #[test_log::test]
+        fn day6_walk_matches_steps() {
+            //Diagonal guards can't use the jump table, so they walk without a copy of the map. The
+            //walled in room can't be escaped, so every obstacle there is a loop
+            for input in [get_test_input(), "#####\n#...#\n#.^.#\n#...#\n#####"] {
+                let map = day6_parse(input).unwrap().with_movement(Movement::Diagonal);
+                let start = map.initial_guards[0];
+                for (p, loc) in map.map.iter() {
+                    if !matches!(loc, Location::Empty) || p == start.0 {
+                        continue;
+                    }
+                    let walked = map.walk_until_exit(start, p, &mut Probe::default());
+                    let mut with_obstacle = map.clone();
+                    with_obstacle.map.set(p, Location::Wall);
+                    let stepped = with_obstacle.simulate_until_exit();
+                    assert_eq!([walked], *stepped, "obstacle at {}", p);
+                }
+            }
+        }
+
+        #[test_log::test]
+        fn day6_parse_errors() {
+            let err = day6_parse("..#\n.^.\n..?").err().unwrap();
+            assert_eq!((err.line, err.column, err.snippet.as_str()), (3, 3, "?"));
+
+            let err = day6_parse("..#\n...").err().unwrap();
+            assert_eq!(err.line, 2);
         }
     }
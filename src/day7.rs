@@ -1,103 +1,302 @@
+use crate::parse::{parse_token, ParseError};
+use std::fmt;
+
 type InputType = Vec<Equation>;
 type OutputType = u64;
 
-#[derive(Debug,Clone)]
-enum Oper {
-    Add,
-    Multiply,
-    Concat, // For concat, we just add the number to the previous number (AS A STRING)
-    // Sure glad I didn't implement this with binary...
+/// Something that can go between two values in an equation, evaluated left to right
+pub trait Operator: fmt::Debug {
+    fn symbol(&self) -> &str;
+
+    /// None if the result isn't a u64 (overflow, negative, dividing by 0...)
+    fn apply(&self, lhs: u64, rhs: u64) -> Option<u64>;
+
+    /// Every `lhs` where `apply(lhs, rhs)` is `target`, so the solver can work backwards and prune.
+    /// None if there's no sensible way to list them, the solver then works forwards instead
+    fn inverse(&self, _target: u64, _rhs: u64) -> Option<Vec<u64>> {
+        None
+    }
 }
 
-type Equation = (u64, Vec<u64>);
+#[derive(Debug)]
+pub struct Add;
 
+impl Operator for Add {
+    fn symbol(&self) -> &str {
+        "+"
+    }
+    fn apply(&self, lhs: u64, rhs: u64) -> Option<u64> {
+        lhs.checked_add(rhs)
+    }
+    fn inverse(&self, target: u64, rhs: u64) -> Option<Vec<u64>> {
+        Some(target.checked_sub(rhs).into_iter().collect())
+    }
+}
 
+#[derive(Debug)]
+pub struct Multiply;
 
-fn valid_line(equa: &Equation, part2: bool) -> bool {
+impl Operator for Multiply {
+    fn symbol(&self) -> &str {
+        "*"
+    }
+    fn apply(&self, lhs: u64, rhs: u64) -> Option<u64> {
+        lhs.checked_mul(rhs)
+    }
+    fn inverse(&self, target: u64, rhs: u64) -> Option<Vec<u64>> {
+        match rhs {
+            //Anything times 0 is 0
+            0 => None,
+            _ => Some(target.is_multiple_of(rhs).then(|| target / rhs).into_iter().collect()),
+        }
+    }
+}
 
-    //This might be pretty bruteforcy, but I don't know what other kind of algorithm to use here?
-    let vals = equa.1.clone();
+/// Stick the digits of rhs on the end of lhs, in any base (10 for the puzzle)
+#[derive(Debug)]
+pub struct Concat {
+    base: u64,
+}
 
-    //The most brute forcy way to do this is to determine the slots (len - 1) and then generate every possible combination of symbols (Add and Multiply in the part 1) to determine if the result is possible.
-    let slots = vals.len() - 1;
+impl Concat {
+    /// None for bases below 2, which don't have digits to stick together
+    pub fn new(base: u64) -> Option<Concat> {
+        (base >= 2).then_some(Concat { base })
+    }
 
-    // Generate every possible combination of symbols
-    //Use itertools
-    let symbols= generate_combinations(slots, part2);
-    //println!("{:?}", symbols);
+    //What to multiply lhs by to make room for rhs's digits
+    fn shift(&self, rhs: u64) -> Option<u64> {
+        let digits = rhs.checked_ilog(self.base).unwrap_or(0) + 1;
+        self.base.checked_pow(digits)
+    }
+}
 
-    for symbol in symbols {
-        let mut current = vals[0];
-        for (i, val) in vals.iter().skip(1).enumerate() {
-            match symbol[i] {
-                Oper::Add => current += val,
-                Oper::Multiply => current *= val,
-                Oper::Concat => current = format!("{}{}", current, val).parse().unwrap(),
-            }
+impl Operator for Concat {
+    fn symbol(&self) -> &str {
+        "||"
+    }
+    fn apply(&self, lhs: u64, rhs: u64) -> Option<u64> {
+        lhs.checked_mul(self.shift(rhs)?)?.checked_add(rhs)
+    }
+    //The target has to end in rhs's digits
+    fn inverse(&self, target: u64, rhs: u64) -> Option<Vec<u64>> {
+        let unconcat = self
+            .shift(rhs)
+            .filter(|shift| target % shift == rhs)
+            .map(|shift| target / shift);
+        Some(unconcat.into_iter().collect())
+    }
+}
+
+/// Only while it stays positive
+#[derive(Debug)]
+pub struct Subtract;
+
+impl Operator for Subtract {
+    fn symbol(&self) -> &str {
+        "-"
+    }
+    fn apply(&self, lhs: u64, rhs: u64) -> Option<u64> {
+        lhs.checked_sub(rhs)
+    }
+    fn inverse(&self, target: u64, rhs: u64) -> Option<Vec<u64>> {
+        Some(target.checked_add(rhs).into_iter().collect())
+    }
+}
+
+/// Integer division, rounding down. Whole ranges of lhs give the same answer, so there's no
+/// inverse and it's solved forwards
+#[derive(Debug)]
+pub struct Divide;
+
+impl Operator for Divide {
+    fn symbol(&self) -> &str {
+        "/"
+    }
+    fn apply(&self, lhs: u64, rhs: u64) -> Option<u64> {
+        lhs.checked_div(rhs)
+    }
+}
+
+#[derive(Debug)]
+pub struct Power;
+
+impl Operator for Power {
+    fn symbol(&self) -> &str {
+        "^"
+    }
+    fn apply(&self, lhs: u64, rhs: u64) -> Option<u64> {
+        lhs.checked_pow(rhs.try_into().ok()?)
+    }
+    //The rhs'th root, if it's a whole number
+    fn inverse(&self, target: u64, rhs: u64) -> Option<Vec<u64>> {
+        if rhs == 0 {
+            //Anything to the 0 is 1
+            return None;
         }
-        if current == equa.0 {
-            return true;
+        let (mut lo, mut hi) = (0, target);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            match self.apply(mid, rhs) {
+                Some(v) if v < target => lo = mid + 1,
+                _ => hi = mid,
+            }
         }
+        Some((self.apply(lo, rhs) == Some(target)).then_some(lo).into_iter().collect())
+    }
+}
+
+/// The puzzle's operators for each part
+pub const PART1: &[&dyn Operator] = &[&Add, &Multiply];
+pub const PART2: &[&dyn Operator] = &[&Add, &Multiply, &Concat { base: 10 }];
+
+pub type Equation = (u64, Vec<u64>);
+
+/// Operators that make an equation's values come to its target, evaluated left to right
+#[derive(Debug, Clone)]
+pub struct Witness<'a> {
+    pub values: Vec<u64>,
+    /// One fewer than `values`, `ops[i]` goes between `values[i]` and `values[i + 1]`
+    pub ops: Vec<&'a dyn Operator>,
+}
 
+impl fmt::Display for Witness<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.values[0])?;
+        for (op, val) in self.ops.iter().zip(&self.values[1..]) {
+            write!(f, " {} {}", op.symbol(), val)?;
+        }
+        Ok(())
     }
+}
 
-    false
+/// Can some choice of `operators` make the equation come out right
+pub fn solvable(equa: &Equation, operators: &[&dyn Operator]) -> bool {
+    search(equa.0, &equa.1, operators, &mut Vec::new(), &mut |_| true)
+}
 
+/// One way to make the equation come out right, if there is one
+pub fn witness<'a>(equa: &Equation, operators: &[&'a dyn Operator]) -> Option<Witness<'a>> {
+    let mut found = None;
+    search(equa.0, &equa.1, operators, &mut Vec::new(), &mut |ops| {
+        found = Some(ops.to_vec());
+        true
+    });
+    found.map(|ops| to_witness(equa, ops))
+}
 
+/// Every way to make the equation come out right
+pub fn witnesses<'a>(equa: &Equation, operators: &[&'a dyn Operator]) -> Vec<Witness<'a>> {
+    let mut found = Vec::new();
+    search(equa.0, &equa.1, operators, &mut Vec::new(), &mut |ops| {
+        found.push(ops.to_vec());
+        false
+    });
+    found.into_iter().map(|ops| to_witness(equa, ops)).collect()
+}
 
+//search finds the operators last first
+fn to_witness<'a>(equa: &Equation, mut ops: Vec<&'a dyn Operator>) -> Witness<'a> {
+    ops.reverse();
+    Witness {
+        values: equa.1.clone(),
+        ops,
+    }
 }
-fn generate_combinations(n: usize, part2: bool) -> Vec<Vec<Oper>> {
-    use Oper::*;
 
-    let mut operators = vec![Add, Multiply];
-    if part2 {
-        operators.push(Concat);
+//Trying every combination of operators is 3^(n-1) for part 2. Instead work backwards from the
+//target: whatever the last operator was, undoing it with the last value has to leave something
+//the rest of the values can make. Most of the time it can't be undone at all (not divisible, the
+//digits don't match), which prunes that whole branch. Operators that can't be undone try
+//everything the rest of the values can make instead.
+//
+//`found` gets the operators (last first) for each way of making the target, and returns true to
+//stop looking. Returns whether it was stopped.
+fn search<'a>(
+    target: u64,
+    vals: &[u64],
+    operators: &[&'a dyn Operator],
+    ops: &mut Vec<&'a dyn Operator>,
+    found: &mut impl FnMut(&[&'a dyn Operator]) -> bool,
+) -> bool {
+    let (&last, rest) = vals.split_last().unwrap();
+    if rest.is_empty() {
+        return target == last && found(ops);
     }
-    let mut results = Vec::new();
-    let mut current = Vec::new();
 
-    fn backtrack(
-        n: usize,
-        operators: &[Oper],
-        current: &mut Vec<Oper>,
-        results: &mut Vec<Vec<Oper>>,
-    ) {
-        if n == 0 {
-            results.push(current.clone());
-            return;
-        }
-        for op in operators {
-            current.push(op.clone());
-            backtrack(n - 1, operators, current, results);
-            current.pop(); // backtrack
+    for &op in operators {
+        ops.push(op);
+        let stop = match op.inverse(target, last) {
+            Some(lhs) => lhs
+                .into_iter()
+                .any(|lhs| search(lhs, rest, operators, ops, found)),
+            None => {
+                let before = ops.len();
+                forward(rest[0], &rest[1..], operators, &mut Vec::new(), &mut |value, prefix| {
+                    if op.apply(value, last) != Some(target) {
+                        return false;
+                    }
+                    ops.extend(prefix.iter().rev());
+                    let stop = found(ops);
+                    ops.truncate(before);
+                    stop
+                })
+            }
+        };
+        ops.pop();
+        if stop {
+            return true;
         }
     }
 
-    backtrack(n, &operators, &mut current, &mut results);
-    results
+    false
 }
 
-
-
+//Every value `vals` can make starting from `acc`, with the operators (first first) that make it.
+//`visit` returns true to stop, returns whether it was stopped
+fn forward<'a>(
+    acc: u64,
+    vals: &[u64],
+    operators: &[&'a dyn Operator],
+    ops: &mut Vec<&'a dyn Operator>,
+    visit: &mut impl FnMut(u64, &[&'a dyn Operator]) -> bool,
+) -> bool {
+    let Some((&next, rest)) = vals.split_first() else {
+        return visit(acc, ops);
+    };
+    for &op in operators {
+        if let Some(acc) = op.apply(acc, next) {
+            ops.push(op);
+            let stop = forward(acc, rest, operators, ops, visit);
+            ops.pop();
+            if stop {
+                return true;
+            }
+        }
+    }
+    false
+}
 
 #[aoc_generator(day7)]
-fn day7_parse(input: &str) -> InputType {
+pub fn day7_parse(input: &str) -> Result<InputType, ParseError> {
     input.lines().map(|line| {
-        let mut parts = line.split(": ");
-        let num = parts.next().unwrap().parse().unwrap();
-        let vals = parts.next().unwrap().split(" ").map(|x| x.parse().unwrap()).collect();
-        (num, vals)
+        let (num, vals) = line
+            .split_once(": ")
+            .ok_or_else(|| ParseError::missing(input, line, "': ' after the test value"))?;
+        let num = parse_token(input, num)?;
+        let vals = vals.split(' ').map(|x| parse_token(input, x)).collect::<Result<_, _>>()?;
+        Ok((num, vals))
     }).collect()
 }
 
 #[aoc(day7, part1)]
 pub fn part1(input: &InputType) -> OutputType {
-    input.iter().filter(|x| valid_line(x,false)).map(|equa| equa.0).sum()
+    input.iter().filter(|x| solvable(x, PART1)).map(|equa| equa.0).sum()
 }
 
 #[aoc(day7, part2)]
 pub fn part2(input: &InputType) -> OutputType {
-    input.iter().filter(|x| valid_line(x,true)).map(|equa| equa.0).sum()
+    input.iter().filter(|x| solvable(x, PART2)).map(|equa| equa.0).sum()
 }
 
 #[cfg(test)]
@@ -119,11 +318,76 @@ mod tests {
 
     #[test]
     fn day7_part1() {
-        assert_eq!(part1(&day7_parse(get_test_input())), 3749);
+        assert_eq!(part1(&day7_parse(get_test_input()).unwrap()), 3749);
     }
 
     #[test]
     fn day7_part2() {
-        assert_eq!(part2(&day7_parse(get_test_input())), 11387);
+        assert_eq!(part2(&day7_parse(get_test_input()).unwrap()), 11387);
+    }
+
+    #[test]
+    fn day7_witnesses() {
+        let input = day7_parse(get_test_input()).unwrap();
+        let shown = |equa, operators| witness(equa, operators).map(|w| w.to_string());
+
+        assert_eq!(shown(&input[1], PART1).unwrap(), "81 * 40 + 27");
+        assert_eq!(shown(&input[2], PART1), None);
+        assert_eq!(shown(&input[4], PART2).unwrap(), "6 * 8 || 6 * 15");
+        assert_eq!(shown(&input[3], PART2).unwrap(), "15 || 6");
+
+        let all = witnesses(&input[1], PART1);
+        let all = all.iter().map(|w| w.to_string()).collect::<Vec<_>>();
+        assert_eq!(all, ["81 * 40 + 27", "81 + 40 * 27"]);
+
+        let zero = (0, vec![5, 3, 0]);
+        assert_eq!(shown(&zero, PART1).unwrap(), "5 + 3 * 0");
+        let all = witnesses(&zero, PART1).len();
+        assert_eq!(all, 2);
+    }
+
+    #[test]
+    fn day7_long_equations() {
+        //Every operator in turn, left to right, over 15 values
+        let vals = vec![3, 7, 2, 9, 4, 6, 1, 8, 5, 2, 3, 7, 4, 1, 9];
+        let target = vals[1..].iter().enumerate().fold(vals[0], |acc, (i, &v)| match i % 3 {
+            0 => acc + v,
+            1 => acc * v,
+            _ => format!("{}{}", acc, v).parse().unwrap(),
+        });
+        assert!(solvable(&(target, vals.clone()), PART2));
+        assert!(!solvable(&(target, vals.clone()), PART1));
+        assert!(!solvable(&(1, vals), PART2));
+
+        assert!(Concat::new(0).is_none() && Concat::new(1).is_none());
+        let concat = Concat::new(10).unwrap();
+        assert_eq!(concat.inverse(1234, 34), Some(vec![12]));
+        assert_eq!(concat.inverse(1234, 4), Some(vec![123]));
+        assert_eq!(concat.inverse(1234, 1234), Some(vec![0]));
+        assert_eq!(concat.inverse(1234, 24), Some(vec![]));
+        assert_eq!(concat.inverse(100, 0), Some(vec![10]));
+        assert!(solvable(&(0, vec![5, 3, 0]), PART1));
+    }
+
+    #[test]
+    fn day7_operator_sets() {
+        let shown = |target, vals: &[u64], operators| {
+            witness(&(target, vals.to_vec()), operators).map(|w| w.to_string())
+        };
+
+        assert_eq!(shown(5, &[10, 3, 2], &[&Subtract]).unwrap(), "10 - 3 - 2");
+        assert_eq!(shown(9, &[3, 10], &[&Subtract]), None);
+        assert_eq!(shown(3, &[20, 6], &[&Divide]).unwrap(), "20 / 6");
+        assert_eq!(shown(64, &[2, 3, 2], &[&Power]).unwrap(), "2 ^ 3 ^ 2");
+        assert_eq!(shown(1, &[7, 0], &[&Power]).unwrap(), "7 ^ 0");
+
+        //Divide has no inverse, the solver has to go forwards for it and backwards for the rest
+        let mixed: &[&dyn Operator] = &[&Add, &Divide, &Multiply];
+        assert_eq!(shown(7, &[20, 6, 4], mixed).unwrap(), "20 / 6 + 4");
+        assert_eq!(shown(14, &[3, 20, 6, 4], mixed).unwrap(), "3 * 20 / 6 + 4");
+
+        //10 || 1 in binary is 101
+        let binary = Concat::new(2).unwrap();
+        assert_eq!(shown(5, &[2, 1], &[&binary]).unwrap(), "2 || 1");
     }
 }
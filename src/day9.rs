@@ -1,3 +1,5 @@
+use crate::parse::ParseError;
+use log::{debug, trace};
 use std::fmt;
 use std::ops::{Deref, DerefMut};
 
@@ -40,11 +42,9 @@ impl HardDrive {
                     c_idx += size;
                 }
                 Space::File(id, size) => {
-                    #[cfg(test)]
-                    println!("See file with ID: {} and size: {} at idx {}", id, size, idx);
+                    trace!("See file with ID: {} and size: {} at idx {}", id, size, idx);
                     for dx in 0..size {
-                        #[cfg(test)]
-                        println!("{} * {} = {}", id, size + c_idx, id * (dx + c_idx));
+                        trace!("{} * {} = {}", id, size + c_idx, id * (dx + c_idx));
                         running_total += id * (dx + c_idx);
                     }
                     c_idx += size;
@@ -88,11 +88,11 @@ impl fmt::Display for Space {
 }
 
 #[aoc_generator(day9)]
-fn day9_parse(input: &str) -> InputType {
+fn day9_parse(input: &str) -> Result<InputType, ParseError> {
     let mut id: u64 = 0;
     let mut is_file = true; //Start
 
-    input.chars().fold(HardDrive(Vec::new()), |mut acc, c| {
+    input.char_indices().try_fold(HardDrive(Vec::new()), |mut acc, (offset, c)| {
         match c {
             '0'..='9' => {
                 let size = c.to_digit(10).unwrap() as u64;
@@ -105,18 +105,17 @@ fn day9_parse(input: &str) -> InputType {
                 is_file = !is_file;
             }
             _ => {
-                panic!("Invalid character in input '{}'", c);
+                return Err(ParseError::at_char(input, offset, c, "Invalid character in input"));
             }
         }
-        acc
+        Ok(acc)
     })
 }
 
 #[aoc(day9, part1)]
 pub fn part1(input: &InputType) -> OutputType {
     let mut spaces: HardDrive = input.clone();
-    #[cfg(test)]
-    println!("Initial: {}", spaces);
+    debug!("Initial: {}", spaces);
 
     // The algorithm is to find empty spaces, then move ahead to the next file, and take as much as possible to try to satisfy the empty space, (which will split the file!)
 
@@ -138,8 +137,7 @@ pub fn part1(input: &InputType) -> OutputType {
                             //We can't take the whole file, so we need to split it
                             spaces[idx] = Space::File(id, remaining);
                             spaces[next_idx] = Space::File(id, file_size - remaining);
-                            #[cfg(test)]
-                            println!(
+                            trace!(
                                 "Splitting file {} into {} and {}",
                                 id,
                                 file_size - remaining,
@@ -151,21 +149,18 @@ pub fn part1(input: &InputType) -> OutputType {
                             spaces[idx] = Space::File(id, file_size);
                             spaces[next_idx] = Space::Empty(0); //Should we just remove it?
                             spaces.insert(idx + 1, Space::Empty(remaining));
-                            #[cfg(test)]
-                            println!("Taking file {} of size {}", id, file_size);
+                            trace!("Taking file {} of size {}", id, file_size);
                         }
                         break;
                     } else {
                         next_idx -= 1; //We found free space at the end, keep looking
                     }
                 }
-                #[cfg(test)]
-                println!("Index: {}, {}", idx, spaces);
+                trace!("Index: {}, {}", idx, spaces);
             }
         }
     }
-    #[cfg(test)]
-    println!("After: {}", spaces);
+    debug!("After: {}", spaces);
 
     spaces.checksum()
 }
@@ -173,8 +168,7 @@ pub fn part1(input: &InputType) -> OutputType {
 #[aoc(day9, part2)]
 pub fn part2(input: &InputType) -> OutputType {
     let mut spaces: HardDrive = input.clone();
-    #[cfg(test)]
-    println!("Initial: {}", spaces);
+    debug!("Initial: {}", spaces);
 
     for idx in (0..spaces.len()).rev() {
         match spaces[idx] {
@@ -195,8 +189,7 @@ pub fn part2(input: &InputType) -> OutputType {
                             if remaining > 0 {
                                 spaces.insert(forward_idx + 1, Space::Empty(remaining));
                             }
-                            #[cfg(test)]
-                            println!("Index: {}, {}", idx, spaces);
+                            trace!("Index: {}, {}", idx, spaces);
                             break;
                         }
                     }
@@ -204,8 +197,7 @@ pub fn part2(input: &InputType) -> OutputType {
             }
         };
     }
-    #[cfg(test)]
-    println!("After: {}", spaces);
+    debug!("After: {}", spaces);
 
     spaces.checksum()
 }
@@ -219,13 +211,19 @@ mod tests {
         "2333133121414131402"
     }
 
-    #[test]
+    #[test_log::test]
     fn day9_part1() {
-        assert_eq!(part1(&day9_parse(get_test_input())), 1928);
+        assert_eq!(part1(&day9_parse(get_test_input()).unwrap()), 1928);
     }
 
-    #[test]
+    #[test_log::test]
     fn day9_part2() {
-        assert_eq!(part2(&day9_parse(get_test_input())), 2858);
+        assert_eq!(part2(&day9_parse(get_test_input()).unwrap()), 2858);
+    }
+
+    #[test_log::test]
+    fn day9_parse_errors() {
+        let err = day9_parse("23331x3").err().unwrap();
+        assert_eq!((err.line, err.column, err.snippet.as_str()), (1, 6, "x"));
     }
 }
@@ -0,0 +1,286 @@
+use crate::geometry::{Direction, Point, Vector};
+use crate::parse::ParseError;
+use std::fmt;
+
+/// Dense, row-major grid. Most of the puzzles are rectangular character maps, so rather than
+/// every day building its own `BTreeMap<(x, y), _>` and scanning for max_x/max_y, parse into this.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Self
+    where
+        T: Clone,
+    {
+        Grid {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+
+    /// Parse a block of text, one row per line, converting each character with `f`.
+    /// Every line must be the same length, panics if it isn't; see `try_parse`.
+    pub fn parse(input: &str, mut f: impl FnMut(char) -> T) -> Self {
+        match Grid::try_parse(input, |c| Ok(f(c))) {
+            Ok(grid) => grid,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Like `parse`, but `f` can reject characters, and ragged rows are reported rather than
+    /// panicking
+    pub fn try_parse(
+        input: &str,
+        mut f: impl FnMut(char) -> Result<T, String>,
+    ) -> Result<Self, ParseError> {
+        let mut width = None;
+        let mut height = 0;
+        let mut cells = Vec::new();
+
+        for line in input.lines() {
+            let before = cells.len();
+            for (idx, c) in line.char_indices() {
+                let cell =
+                    f(c).map_err(|e| ParseError::at(input, &line[idx..idx + c.len_utf8()], e))?;
+                cells.push(cell);
+            }
+            let row_width = cells.len() - before;
+
+            match width {
+                None => width = Some(row_width),
+                Some(w) if w != row_width => {
+                    return Err(ParseError::at(
+                        input,
+                        line,
+                        format!("row is {} wide, expected {}", row_width, w),
+                    ))
+                }
+                Some(_) => {}
+            }
+            height += 1;
+        }
+
+        Ok(Grid {
+            width: width.unwrap_or(0),
+            height,
+            cells,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn max_x(&self) -> i64 {
+        self.width as i64 - 1
+    }
+
+    pub fn max_y(&self) -> i64 {
+        self.height as i64 - 1
+    }
+
+    pub fn in_bounds(&self, p: Point) -> bool {
+        p.x >= 0 && p.y >= 0 && p.x <= self.max_x() && p.y <= self.max_y()
+    }
+
+    fn index(&self, p: Point) -> Option<usize> {
+        if self.in_bounds(p) {
+            Some(p.y as usize * self.width + p.x as usize)
+        } else {
+            None
+        }
+    }
+
+    fn coord(&self, idx: usize) -> Point {
+        Point::new((idx % self.width) as i64, (idx / self.width) as i64)
+    }
+
+    pub fn get(&self, p: Point) -> Option<&T> {
+        self.index(p).map(|idx| &self.cells[idx])
+    }
+
+    pub fn get_mut(&mut self, p: Point) -> Option<&mut T> {
+        self.index(p).map(|idx| &mut self.cells[idx])
+    }
+
+    /// Replace the value at `p`, returning the old one (None if out of bounds, in which case
+    /// nothing is stored)
+    pub fn set(&mut self, p: Point, val: T) -> Option<T> {
+        self.get_mut(p).map(|cell| std::mem::replace(cell, val))
+    }
+
+    /// Every cell in row-major order
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> + '_ {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(idx, c)| (self.coord(idx), c))
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = Point> + '_ {
+        (0..self.cells.len()).map(|idx| self.coord(idx))
+    }
+
+    pub fn find(&self, mut pred: impl FnMut(&T) -> bool) -> Option<Point> {
+        self.iter().find(|(_, c)| pred(c)).map(|(p, _)| p)
+    }
+
+    fn neighbours<'a>(
+        &'a self,
+        p: Point,
+        dirs: &'a [Direction],
+    ) -> impl Iterator<Item = (Point, &'a T)> + 'a {
+        dirs.iter().filter_map(move |dir| {
+            let n = p.step(*dir);
+            self.get(n).map(|c| (n, c))
+        })
+    }
+
+    /// Orthogonal neighbours that are on the grid
+    pub fn neighbours4(&self, p: Point) -> impl Iterator<Item = (Point, &T)> + '_ {
+        self.neighbours(p, &Direction::CARDINALS)
+    }
+
+    /// Orthogonal and diagonal neighbours that are on the grid
+    pub fn neighbours8(&self, p: Point) -> impl Iterator<Item = (Point, &T)> + '_ {
+        self.neighbours(p, &Direction::ALL)
+    }
+
+    /// Walk from `start` (inclusive) in steps of `delta` until falling off the grid
+    pub fn ray(&self, start: Point, delta: Vector) -> impl Iterator<Item = (Point, &T)> + '_ {
+        let mut next = start;
+        std::iter::from_fn(move || {
+            let p = next;
+            next = p + delta;
+            self.get(p).map(|c| (p, c))
+        })
+    }
+
+    /// Empty if `y` is off the grid, like `get`
+    pub fn row(&self, y: usize) -> impl Iterator<Item = &T> + '_ {
+        let cells = if y < self.height {
+            &self.cells[y * self.width..(y + 1) * self.width]
+        } else {
+            &[]
+        };
+        cells.iter()
+    }
+
+    /// Empty if `x` is off the grid, like `get`
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> + '_ {
+        let len = if x < self.width { self.height } else { 0 };
+        self.cells
+            .iter()
+            .skip(x)
+            .step_by(self.width.max(1))
+            .take(len)
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> + '_ {
+        self.cells.chunks(self.width.max(1))
+    }
+
+    /// Every top-left to bottom-right diagonal, starting from the bottom-left corner
+    pub fn diagonals(&self) -> impl Iterator<Item = Vec<(Point, &T)>> + '_ {
+        let starts = (0..self.height as i64)
+            .rev()
+            .map(|y| Point::new(0, y))
+            .chain((1..self.width as i64).map(|x| Point::new(x, 0)));
+        starts.map(move |s| self.ray(s, Direction::SouthEast.vector()).collect())
+    }
+
+    /// Every top-right to bottom-left diagonal, starting from the top-left corner
+    pub fn anti_diagonals(&self) -> impl Iterator<Item = Vec<(Point, &T)>> + '_ {
+        let max_x = self.max_x();
+        let starts = (0..self.width as i64)
+            .map(|x| Point::new(x, 0))
+            .chain((1..self.height as i64).map(move |y| Point::new(max_x, y)));
+        starts.map(move |s| self.ray(s, Direction::SouthWest.vector()).collect())
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            for c in row {
+                write!(f, "{}", c)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn get_test_input() -> &'static str {
+        "abc
+def"
+    }
+
+    #[test]
+    fn grid_parse_and_get() {
+        let grid = Grid::parse(get_test_input(), |c| c);
+        assert_eq!(grid.width(), 3);
+        assert_eq!(grid.height(), 2);
+        assert_eq!(grid.get(Point::new(2, 1)), Some(&'f'));
+        assert_eq!(grid.get(Point::new(3, 0)), None);
+        assert_eq!(grid.get(Point::new(0, -1)), None);
+        assert_eq!(grid.to_string(), "abc\ndef\n");
+    }
+
+    #[test]
+    fn grid_neighbours() {
+        let grid = Grid::parse(get_test_input(), |c| c);
+        let n4 = grid
+            .neighbours4(Point::new(0, 0))
+            .map(|(_, c)| *c)
+            .collect::<String>();
+        assert_eq!(n4, "bd");
+        let n8 = grid
+            .neighbours8(Point::new(1, 0))
+            .map(|(_, c)| *c)
+            .collect::<String>();
+        assert_eq!(n8, "cfeda");
+    }
+
+    #[test]
+    fn grid_lines() {
+        let grid = Grid::parse(get_test_input(), |c| c);
+        assert_eq!(grid.row(1).collect::<String>(), "def");
+        assert_eq!(grid.column(1).collect::<String>(), "be");
+        assert_eq!(grid.row(2).count(), 0);
+        assert_eq!(grid.column(3).count(), 0);
+        assert_eq!(grid.column(4).count(), 0);
+        let diagonals = grid
+            .diagonals()
+            .map(|d| d.iter().map(|(_, c)| **c).collect::<String>())
+            .collect::<Vec<_>>();
+        assert_eq!(diagonals, vec!["d", "ae", "bf", "c"]);
+        let anti = grid
+            .anti_diagonals()
+            .map(|d| d.iter().map(|(_, c)| **c).collect::<String>())
+            .collect::<Vec<_>>();
+        assert_eq!(anti, vec!["a", "bd", "ce", "f"]);
+    }
+}
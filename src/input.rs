@@ -0,0 +1,144 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+/// Where a day's puzzle input comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputSource {
+    /// The aoc-runner layout, `input/<year>/day<N>.txt`
+    Default,
+    File(PathBuf),
+    Stdin,
+}
+
+/// Files that git-crypt hasn't decrypted start with this header
+const GIT_CRYPT_MAGIC: &[u8] = b"\0GITCRYPT\0";
+
+#[derive(Debug)]
+pub enum InputError {
+    Io(String, io::Error),
+    Encrypted(String),
+    NotUtf8(String),
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputError::Io(name, e) => write!(f, "couldn't read {}: {}", name, e),
+            InputError::Encrypted(name) => write!(
+                f,
+                "{} is encrypted, run `git-crypt unlock` to decrypt the puzzle inputs",
+                name
+            ),
+            InputError::NotUtf8(name) => write!(f, "{} isn't valid UTF-8", name),
+        }
+    }
+}
+
+impl Error for InputError {}
+
+pub fn default_path(day: u32) -> PathBuf {
+    PathBuf::from(format!("input/{}/day{}.txt", crate::YEAR, day))
+}
+
+impl InputSource {
+    fn name(&self, day: u32) -> String {
+        match self {
+            InputSource::Default => default_path(day).display().to_string(),
+            InputSource::File(path) => path.display().to_string(),
+            InputSource::Stdin => "stdin".to_string(),
+        }
+    }
+}
+
+/// Read the raw input for `day`
+pub fn load(day: u32, source: &InputSource) -> Result<String, InputError> {
+    let name = source.name(day);
+    let bytes = match source {
+        InputSource::Default => fs::read(default_path(day)),
+        InputSource::File(path) => fs::read(path),
+        InputSource::Stdin => {
+            let mut buf = Vec::new();
+            io::stdin().read_to_end(&mut buf).map(|_| buf)
+        }
+    }
+    .map_err(|e| InputError::Io(name.clone(), e))?;
+
+    decode(name, bytes)
+}
+
+/// Make input parse the same no matter what machine or editor it came from: drops a UTF-8 BOM,
+/// turns CRLF (and lone CR) line endings into LF, strips trailing whitespace from each line and
+/// drops trailing blank lines. The result has no trailing newline.
+pub fn normalise(text: &str) -> String {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+
+    let mut out = text
+        .split('\n')
+        .map(|line| line.trim_end())
+        .collect::<Vec<_>>()
+        .join("\n");
+    out.truncate(out.trim_end().len());
+    out
+}
+
+/// Split normalised input into blank-line separated sections. Runs of blank lines count as one
+/// separator, so nothing returned is empty.
+pub fn sections(input: &str) -> impl Iterator<Item = &str> {
+    input
+        .split("\n\n")
+        .map(|section| section.trim_matches('\n'))
+        .filter(|section| !section.is_empty())
+}
+
+//Refuse to hand ciphertext to a generator, it'll only fail in some confusing way
+fn decode(name: String, bytes: Vec<u8>) -> Result<String, InputError> {
+    if bytes.starts_with(GIT_CRYPT_MAGIC) {
+        return Err(InputError::Encrypted(name));
+    }
+
+    String::from_utf8(bytes).map_err(|_| InputError::NotUtf8(name))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn input_detects_git_crypt() {
+        let mut bytes = GIT_CRYPT_MAGIC.to_vec();
+        bytes.extend([0x9e, 0xad, 0x48, 0xf9]);
+        let err = decode("day1.txt".to_string(), bytes).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day1.txt is encrypted, run `git-crypt unlock` to decrypt the puzzle inputs"
+        );
+
+        let text = decode("day1.txt".to_string(), b"3   4\n".to_vec()).unwrap();
+        assert_eq!(text, "3   4\n");
+    }
+
+    #[test]
+    fn input_normalise() {
+        assert_eq!(
+            normalise("\u{feff}47|53 \r\n97|13\r\n\r\n75,47\r\n\r\n"),
+            "47|53\n97|13\n\n75,47"
+        );
+        assert_eq!(normalise("125 17\n"), "125 17");
+        assert_eq!(normalise("a\rb\t\n"), "a\nb");
+        assert_eq!(normalise(""), "");
+    }
+
+    #[test]
+    fn input_sections() {
+        let text = "47|53\n97|13\n\n\n\n75,47\n\n61,13";
+        assert_eq!(
+            sections(text).collect::<Vec<_>>(),
+            vec!["47|53\n97|13", "75,47", "61,13"]
+        );
+    }
+}
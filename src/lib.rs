@@ -13,6 +13,15 @@ extern crate itertools;
 extern crate rayon;
 extern crate regex;
 
+pub mod answers;
+pub mod geometry;
+pub mod grid;
+pub mod input;
+pub mod parse;
+pub mod render;
+pub mod runner;
+pub mod scaffold;
+
 pub mod day1;
 pub mod day2;
 pub mod day3;
@@ -1,7 +1,500 @@
 extern crate aoc2024;
-extern crate aoc_runner;
-extern crate aoc_runner_derive;
 
-use aoc_runner_derive::aoc_main;
+use aoc2024::answers::{self, Answers};
+use aoc2024::day5;
+use aoc2024::day6;
+use aoc2024::day7;
+use aoc2024::geometry::Point;
+use aoc2024::input::{self, InputSource};
+use aoc2024::runner::{self, RunResult};
+use aoc2024::scaffold;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::thread;
+use std::time::Duration;
 
-aoc_main! { lib = aoc2024 }
+const USAGE: &str = "Usage: aoc2024 run (--day N [--part P] [--input PATH|-] | --all)
+       aoc2024 verify [--day N] [--part P] [--answers PATH]
+       aoc2024 new-day N
+       aoc2024 replay [--input PATH|-] [--obstacle X,Y] [--delay MS] [--export csv|json]
+       aoc2024 dot [--input PATH|-] [--update N]
+       aoc2024 witness [--input PATH|-] [--part P] [--all]
+
+Commands:
+  run      Run solvers and print a table of answers with parse and solve times
+  verify   Run solvers against the real inputs and check them against the known answers
+  new-day  Create src/dayN.rs from src/template.rs, register it and add an example input
+  replay   Animate the day 6 guards' walk in the terminal, or export it
+  dot      Print the day 5 page ordering rules as a Graphviz graph
+  witness  Show the operators that solve each day 7 equation (part 2's by default)
+
+Options:
+  --day N          Only run day N
+  --part P         Only run part P (1 or 2)
+  --all            Run every registered day (witness: every solution, not just the first)
+  --input PATH     Read the input from PATH instead of input/2024/dayN.txt, '-' for stdin
+  --answers PATH   Known answers to verify against (default answers.toml)
+  --obstacle X,Y   Put an extra wall at X,Y before the guards set off
+  --delay MS       Time between replay frames (default 50)
+  --export FORMAT  Print the guards' path as csv or json instead of animating it
+  --update N       Only draw the pages in the Nth update (from 0), with broken rules in red
+
+Set RUST_LOG to see solver tracing, e.g. RUST_LOG=aoc2024::day6=debug";
+
+const DEFAULT_ANSWERS: &str = "answers.toml";
+const DEFAULT_DELAY: Duration = Duration::from_millis(50);
+
+#[derive(Debug)]
+struct RunOptions {
+    day: Option<u32>,
+    part: Option<u32>,
+    source: InputSource,
+}
+
+#[derive(Debug)]
+struct VerifyOptions {
+    day: Option<u32>,
+    part: Option<u32>,
+    answers: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Export {
+    Csv,
+    Json,
+}
+
+#[derive(Debug)]
+struct ReplayOptions {
+    source: InputSource,
+    obstacle: Option<Point>,
+    delay: Duration,
+    export: Option<Export>,
+}
+
+#[derive(Debug)]
+struct DotOptions {
+    source: InputSource,
+    update: Option<usize>,
+}
+
+#[derive(Debug)]
+struct WitnessOptions {
+    source: InputSource,
+    part: u32,
+    all: bool,
+}
+
+enum Command {
+    Run(RunOptions),
+    Verify(VerifyOptions),
+    NewDay(u32),
+    Replay(ReplayOptions),
+    Dot(DotOptions),
+    Witness(WitnessOptions),
+    Help,
+}
+
+fn parse_number(flag: &str, value: Option<String>) -> Result<u32, String> {
+    let value = value.ok_or_else(|| format!("{} needs a value", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("{} expects a number, got '{}'", flag, value))
+}
+
+fn parse_source(flag: &str, value: Option<String>) -> Result<InputSource, String> {
+    match value.as_deref() {
+        Some("-") => Ok(InputSource::Stdin),
+        Some(path) => Ok(InputSource::File(PathBuf::from(path))),
+        None => Err(format!("{} needs a value", flag)),
+    }
+}
+
+fn parse_run(mut args: impl Iterator<Item = String>) -> Result<RunOptions, String> {
+    let mut opts = RunOptions {
+        day: None,
+        part: None,
+        source: InputSource::Default,
+    };
+    let mut all = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--day" | "-d" => opts.day = Some(parse_number(&arg, args.next())?),
+            "--part" | "-p" => opts.part = Some(parse_number(&arg, args.next())?),
+            "--all" | "-a" => all = true,
+            "--input" | "-i" => opts.source = parse_source(&arg, args.next())?,
+            _ => return Err(format!("unknown option '{}'", arg)),
+        }
+    }
+
+    match (all, opts.day) {
+        (true, Some(_)) => Err("--all and --day can't be used together".to_string()),
+        (false, None) => Err("pick a day with --day N, or use --all".to_string()),
+        (true, None) if opts.source != InputSource::Default => {
+            Err("--input only makes sense for a single --day".to_string())
+        }
+        _ => Ok(opts),
+    }
+}
+
+fn parse_verify(mut args: impl Iterator<Item = String>) -> Result<VerifyOptions, String> {
+    let mut opts = VerifyOptions {
+        day: None,
+        part: None,
+        answers: PathBuf::from(DEFAULT_ANSWERS),
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--day" | "-d" => opts.day = Some(parse_number(&arg, args.next())?),
+            "--part" | "-p" => opts.part = Some(parse_number(&arg, args.next())?),
+            "--answers" => {
+                opts.answers = PathBuf::from(
+                    args.next()
+                        .ok_or_else(|| format!("{} needs a value", arg))?,
+                )
+            }
+            _ => return Err(format!("unknown option '{}'", arg)),
+        }
+    }
+
+    Ok(opts)
+}
+
+fn parse_replay(mut args: impl Iterator<Item = String>) -> Result<ReplayOptions, String> {
+    let mut opts = ReplayOptions {
+        source: InputSource::Default,
+        obstacle: None,
+        delay: DEFAULT_DELAY,
+        export: None,
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--input" | "-i" => opts.source = parse_source(&arg, args.next())?,
+            "--obstacle" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("{} needs a value", arg))?;
+                let point = value
+                    .split_once(',')
+                    .and_then(|(x, y)| Some(Point::new(x.parse().ok()?, y.parse().ok()?)));
+                opts.obstacle =
+                    Some(point.ok_or_else(|| format!("{} expects X,Y, got '{}'", arg, value))?);
+            }
+            "--delay" => {
+                opts.delay = Duration::from_millis(parse_number(&arg, args.next())?.into())
+            }
+            "--export" => {
+                opts.export = match args.next().as_deref() {
+                    Some("csv") => Some(Export::Csv),
+                    Some("json") => Some(Export::Json),
+                    Some(other) => return Err(format!("can't export as '{}'", other)),
+                    None => return Err(format!("{} needs a value", arg)),
+                }
+            }
+            _ => return Err(format!("unknown option '{}'", arg)),
+        }
+    }
+
+    Ok(opts)
+}
+
+fn parse_dot(mut args: impl Iterator<Item = String>) -> Result<DotOptions, String> {
+    let mut opts = DotOptions {
+        source: InputSource::Default,
+        update: None,
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--input" | "-i" => opts.source = parse_source(&arg, args.next())?,
+            "--update" => opts.update = Some(parse_number(&arg, args.next())? as usize),
+            _ => return Err(format!("unknown option '{}'", arg)),
+        }
+    }
+
+    Ok(opts)
+}
+
+fn parse_witness(mut args: impl Iterator<Item = String>) -> Result<WitnessOptions, String> {
+    let mut opts = WitnessOptions {
+        source: InputSource::Default,
+        part: 2,
+        all: false,
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--input" | "-i" => opts.source = parse_source(&arg, args.next())?,
+            "--part" | "-p" => opts.part = parse_number(&arg, args.next())?,
+            "--all" | "-a" => opts.all = true,
+            _ => return Err(format!("unknown option '{}'", arg)),
+        }
+    }
+
+    match opts.part {
+        1 | 2 => Ok(opts),
+        _ => Err("--part has to be 1 or 2".to_string()),
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    match args.next().as_deref() {
+        Some("run") => parse_run(args).map(Command::Run),
+        Some("verify") => parse_verify(args).map(Command::Verify),
+        Some("new-day") => {
+            let day = parse_number("new-day", args.next())?;
+            match args.next() {
+                Some(extra) => Err(format!("unexpected argument '{}'", extra)),
+                None => Ok(Command::NewDay(day)),
+            }
+        }
+        Some("replay") => parse_replay(args).map(Command::Replay),
+        Some("dot") => parse_dot(args).map(Command::Dot),
+        Some("witness") => parse_witness(args).map(Command::Witness),
+        Some("help") | Some("--help") | Some("-h") | None => Ok(Command::Help),
+        Some(other) => Err(format!("unknown command '{}'", other)),
+    }
+}
+
+/// Run the selected solvers, returning their results and whether every input could be loaded
+fn solve_all(day: Option<u32>, part: Option<u32>, source: &InputSource) -> (Vec<RunResult>, bool) {
+    let mut results = Vec::new();
+    let mut loaded_all = true;
+    let mut loaded = None;
+
+    for solver in runner::select(day, part) {
+        //Both parts of a day share the input, only read it once (stdin can't be read twice anyway)
+        if loaded.as_ref().is_none_or(|(day, _)| *day != solver.day) {
+            let text = input::load(solver.day, source);
+            if let Err(e) = &text {
+                eprintln!("Day {}: {}", solver.day, e);
+                loaded_all = false;
+            }
+            loaded = Some((solver.day, text));
+        }
+        let Some((_, Ok(text))) = &loaded else {
+            continue;
+        };
+
+        results.push(runner::run(solver, text));
+    }
+
+    (results, loaded_all)
+}
+
+fn run(opts: RunOptions) -> ExitCode {
+    if runner::select(opts.day, opts.part).next().is_none() {
+        eprintln!("No solvers registered for that day/part");
+        return ExitCode::FAILURE;
+    }
+
+    let (results, mut ok) = solve_all(opts.day, opts.part, &opts.source);
+    ok &= results.iter().all(|r| r.answer.is_ok());
+
+    if !results.is_empty() {
+        print!("{}", runner::format_table(&results));
+    }
+
+    if ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn verify(opts: VerifyOptions) -> ExitCode {
+    let known = match fs::read_to_string(&opts.answers) {
+        Ok(text) => Answers::parse(&text),
+        Err(e) => {
+            eprintln!("couldn't read {}: {}", opts.answers.display(), e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let known = match known {
+        Ok(known) => known,
+        Err(e) => {
+            eprintln!("{}: {}", opts.answers.display(), e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let (results, loaded_all) = solve_all(opts.day, opts.part, &InputSource::Default);
+    let checked = results
+        .iter()
+        .map(|r| (r, known.check(r)))
+        .collect::<Vec<_>>();
+
+    print!("{}", answers::format_report(&checked));
+
+    if loaded_all && !checked.iter().any(|(_, v)| v.is_failure()) {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn new_day(day: u32) -> ExitCode {
+    match scaffold::new_day(Path::new("."), day) {
+        Ok(created) => {
+            for path in created {
+                println!("Created {}", path.display());
+            }
+            println!("Registered day{} in src/lib.rs and src/runner.rs", day);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn replay(opts: ReplayOptions) -> ExitCode {
+    let text = match input::load(6, &opts.source) {
+        Ok(text) => input::normalise(&text),
+        Err(e) => {
+            eprintln!("Day 6: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let map = match day6::day6_parse(&text) {
+        Ok(map) => map,
+        Err(e) => {
+            eprintln!("Day 6: parse error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let map = match opts.obstacle {
+        Some(obstacle) => map.with_obstacle(obstacle),
+        None => Ok(map),
+    };
+    let mut map = match map {
+        Ok(map) => map.recording(),
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let outcomes = map.simulate_until_exit();
+    let trajectory = map.trajectory().unwrap();
+
+    match opts.export {
+        Some(Export::Csv) => print!("{}", trajectory.to_csv()),
+        Some(Export::Json) => print!("{}", trajectory.to_json()),
+        None => {
+            let colour = colored::control::SHOULD_COLORIZE.should_colorize();
+            for frame in map.frames(trajectory, colour) {
+                //Clear the screen and go back to the top left before each frame
+                print!("\x1b[2J\x1b[H{}", frame);
+                let _ = io::stdout().flush();
+                thread::sleep(opts.delay);
+            }
+            for (guard, outcome) in outcomes.iter().enumerate() {
+                println!("Guard {}: {:?}", guard, outcome);
+            }
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn dot(opts: DotOptions) -> ExitCode {
+    let text = match input::load(5, &opts.source) {
+        Ok(text) => input::normalise(&text),
+        Err(e) => {
+            eprintln!("Day 5: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let (rules, updates) = match day5::day5_parse(&text) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("Day 5: parse error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let update = match opts.update {
+        Some(n) => match updates.get(n) {
+            Some(update) => Some(update.as_slice()),
+            None => {
+                eprintln!(
+                    "no update {}, there are {} (numbered from 0)",
+                    n,
+                    updates.len()
+                );
+                return ExitCode::FAILURE;
+            }
+        },
+        None => None,
+    };
+    print!("{}", day5::to_dot(&rules, update));
+
+    ExitCode::SUCCESS
+}
+
+fn witness(opts: WitnessOptions) -> ExitCode {
+    let text = match input::load(7, &opts.source) {
+        Ok(text) => input::normalise(&text),
+        Err(e) => {
+            eprintln!("Day 7: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let equations = match day7::day7_parse(&text) {
+        Ok(equations) => equations,
+        Err(e) => {
+            eprintln!("Day 7: parse error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let operators = if opts.part == 2 {
+        day7::PART2
+    } else {
+        day7::PART1
+    };
+    for equa in &equations {
+        let found = if opts.all {
+            day7::witnesses(equa, operators)
+        } else {
+            day7::witness(equa, operators).into_iter().collect()
+        };
+        if found.is_empty() {
+            println!("{}: no solution", equa.0);
+        }
+        for w in found {
+            println!("{}: {}", equa.0, w);
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn main() -> ExitCode {
+    //Solvers trace through `log`, e.g. RUST_LOG=aoc2024::day6=trace
+    env_logger::init();
+
+    match parse_args(std::env::args().skip(1)) {
+        Ok(Command::Run(opts)) => run(opts),
+        Ok(Command::Verify(opts)) => verify(opts),
+        Ok(Command::NewDay(day)) => new_day(day),
+        Ok(Command::Replay(opts)) => replay(opts),
+        Ok(Command::Dot(opts)) => dot(opts),
+        Ok(Command::Witness(opts)) => witness(opts),
+        Ok(Command::Help) => {
+            println!("{}", USAGE);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Something in the puzzle input didn't look the way the generator expected. Line and column are
+/// 1-based so they match what an editor shows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub snippet: String,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(
+        line: usize,
+        column: usize,
+        snippet: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        ParseError {
+            line,
+            column,
+            snippet: snippet.into(),
+            message: message.into(),
+        }
+    }
+
+    /// Build an error pointing at `snippet`, which must be a slice of `input` (what you get back
+    /// from `lines()`, `split()` and friends). The line/column are worked out from where the
+    /// slice sits inside the input, so generators don't have to track offsets themselves.
+    pub fn at(input: &str, snippet: &str, message: impl Into<String>) -> Self {
+        let offset = (snippet.as_ptr() as usize).wrapping_sub(input.as_ptr() as usize);
+        let within = offset <= input.len() && snippet.len() <= input.len() - offset;
+        debug_assert!(within, "{:?} isn't a slice of the input", snippet);
+        //Anything else gets pointed at the start, rather than panicking while reporting an error
+        let offset = if within {
+            input.floor_char_boundary(offset)
+        } else {
+            0
+        };
+
+        let before = &input[..offset];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+
+        ParseError::new(line, column, snippet, message)
+    }
+
+    /// Point at the end of `within` (a slice of `input`), for when something we expected to find
+    /// there is missing
+    pub fn missing(input: &str, within: &str, what: &str) -> Self {
+        ParseError::at(input, &within[within.len()..], format!("expected {}", what))
+    }
+
+    /// Point at a single character, `offset` is the byte offset of `c` inside `input`
+    pub fn at_char(input: &str, offset: usize, c: char, message: impl Into<String>) -> Self {
+        ParseError::at(input, &input[offset..offset + c.len_utf8()], message)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )?;
+        if !self.snippet.is_empty() {
+            write!(f, " (at {:?})", self.snippet)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ParseError {}
+
+/// `str::parse` for a token that is a slice of `input`, with the failure located in the input
+pub fn parse_token<T>(input: &str, token: &str) -> Result<T, ParseError>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    token
+        .parse()
+        .map_err(|e| ParseError::at(input, token, format!("{}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn get_test_input() -> &'static str {
+        "1 2
+3 x4"
+    }
+
+    #[test]
+    fn parse_error_location() {
+        let input = get_test_input();
+        let token = input.lines().nth(1).unwrap().split(' ').nth(1).unwrap();
+        let err = parse_token::<u64>(input, token).unwrap_err();
+        assert_eq!((err.line, err.column), (2, 3));
+        assert_eq!(err.snippet, "x4");
+        assert_eq!(
+            err.to_string(),
+            "line 2, column 3: invalid digit found in string (at \"x4\")"
+        );
+    }
+
+    #[test]
+    fn parse_error_missing() {
+        let input = get_test_input();
+        let line = input.lines().next().unwrap();
+        let err = ParseError::missing(input, line, "a third number");
+        assert_eq!((err.line, err.column), (1, 4));
+        assert_eq!(err.to_string(), "line 1, column 4: expected a third number");
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "isn't a slice of the input")]
+    fn parse_error_foreign_snippet() {
+        let owned = get_test_input().lines().next().unwrap().to_string();
+        ParseError::at(get_test_input(), &owned, "not from the input");
+    }
+}
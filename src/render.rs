@@ -0,0 +1,149 @@
+use crate::geometry::Point;
+use crate::grid::Grid;
+use colored::Color;
+use std::collections::BTreeSet;
+
+/// A set of cells to draw differently from the underlying grid, e.g. visited cells, antinodes
+#[derive(Clone, Debug)]
+pub struct Layer {
+    points: BTreeSet<Point>,
+    glyph: Option<char>,
+    colour: Option<Color>,
+    fallback: Option<char>,
+}
+
+impl Layer {
+    pub fn new(points: impl IntoIterator<Item = Point>) -> Self {
+        Layer {
+            points: points.into_iter().collect(),
+            glyph: None,
+            colour: None,
+            fallback: None,
+        }
+    }
+
+    /// Draw these cells as `glyph` instead of the grid's own character
+    pub fn glyph(mut self, glyph: char) -> Self {
+        self.glyph = Some(glyph);
+        self
+    }
+
+    pub fn colour(mut self, colour: Color) -> Self {
+        self.colour = Some(colour);
+        self
+    }
+
+    /// Add a cell, for layers that grow as they're drawn (e.g. a path)
+    pub fn insert(&mut self, point: Point) {
+        self.points.insert(point);
+    }
+
+    /// Glyph to use when colour is off, so a colour-only layer is still visible
+    pub fn fallback(mut self, glyph: char) -> Self {
+        self.fallback = Some(glyph);
+        self
+    }
+}
+
+/// Draws a grid with any number of highlight layers on top, later layers win. Colour follows
+/// the `colored` crate's detection (NO_COLOR, CLICOLOR, is stdout a terminal) unless overridden.
+pub struct Render<'a, T> {
+    grid: &'a Grid<T>,
+    layers: Vec<Layer>,
+    colour: bool,
+    unmarked: Option<char>,
+}
+
+impl<'a, T> Render<'a, T> {
+    pub fn new(grid: &'a Grid<T>) -> Self {
+        Render {
+            grid,
+            layers: Vec::new(),
+            colour: colored::control::SHOULD_COLORIZE.should_colorize(),
+            unmarked: None,
+        }
+    }
+
+    pub fn layer(mut self, layer: Layer) -> Self {
+        self.layers.push(layer);
+        self
+    }
+
+    /// The layers so far, bottom first, to change them between renders
+    pub fn layers_mut(&mut self) -> &mut Vec<Layer> {
+        &mut self.layers
+    }
+
+    pub fn colour(mut self, colour: bool) -> Self {
+        self.colour = colour;
+        self
+    }
+
+    /// Draw every cell that isn't in a layer as `glyph`, to make the highlights stand out
+    pub fn unmarked(mut self, glyph: char) -> Self {
+        self.unmarked = Some(glyph);
+        self
+    }
+
+    /// Render to a string, `cell` gives the character for each grid value
+    pub fn render(&self, cell: impl Fn(&T) -> char) -> String {
+        let mut out = String::new();
+        for (p, value) in self.grid.iter() {
+            let base = cell(value);
+            match self.layers.iter().rev().find(|l| l.points.contains(&p)) {
+                Some(layer) => self.push_marked(&mut out, layer, base),
+                None => out.push(self.unmarked.unwrap_or(base)),
+            }
+            if p.x == self.grid.max_x() {
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    pub fn print(&self, cell: impl Fn(&T) -> char) {
+        print!("{}", self.render(cell));
+    }
+
+    fn push_marked(&self, out: &mut String, layer: &Layer, base: char) {
+        match (self.colour, layer.colour) {
+            (true, Some(colour)) => {
+                let glyph = layer.glyph.unwrap_or(base);
+                out.push_str(&format!("\x1b[{}m{}\x1b[0m", colour.to_fg_str(), glyph));
+            }
+            _ => out.push(layer.glyph.or(layer.fallback).unwrap_or(base)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn get_test_input() -> &'static str {
+        "ab.
+.#."
+    }
+
+    #[test]
+    fn render_layers() {
+        let grid = Grid::parse(get_test_input(), |c| c);
+        let render = Render::new(&grid)
+            .colour(false)
+            .layer(Layer::new([Point::new(0, 0), Point::new(2, 1)]).glyph('X'))
+            .layer(
+                Layer::new([Point::new(2, 1)])
+                    .colour(Color::Green)
+                    .fallback('O'),
+            )
+            .layer(Layer::new([Point::new(1, 0)]).colour(Color::Red));
+        assert_eq!(render.render(|c| *c), "Xb.\n.#O\n");
+
+        let render = render.colour(true).unmarked(' ');
+        assert_eq!(
+            render.render(|c| *c),
+            "X\x1b[31mb\x1b[0m \n  \x1b[32m.\x1b[0m\n"
+        );
+    }
+}
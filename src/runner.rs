@@ -0,0 +1,230 @@
+use crate::input;
+use aoc_runner::{ArcStr, Runner};
+use log::debug;
+use std::error::Error;
+use std::fmt::Write;
+use std::panic::{self, AssertUnwindSafe};
+use std::time::{Duration, Instant};
+
+/// Runs the day's generator on the input, handing back something that can solve the part
+pub type Build = fn(ArcStr) -> Result<Box<dyn Runner>, Box<dyn Error>>;
+
+/// A registered `#[aoc(dayN, partM)]` solution
+pub struct Solver {
+    pub day: u32,
+    pub part: u32,
+    pub build: Build,
+}
+
+// aoc_lib! generates a `DayNPartM` trait on `Factory` for every solution, wire those up here so
+// we aren't stuck with aoc_main!
+macro_rules! solver {
+    ($day:literal, $part:literal, $t:ident::$f:ident) => {
+        Solver {
+            day: $day,
+            part: $part,
+            build: <crate::Factory as crate::$t>::$f,
+        }
+    };
+}
+
+pub static SOLVERS: &[Solver] = &[
+    solver!(1, 1, Day1Part1::day1_part1),
+    solver!(1, 2, Day1Part2::day1_part2),
+    solver!(2, 1, Day2Part1::day2_part1),
+    solver!(2, 2, Day2Part2::day2_part2),
+    solver!(3, 1, Day3Part1::day3_part1),
+    solver!(3, 2, Day3Part2::day3_part2),
+    solver!(4, 1, Day4Part1::day4_part1),
+    solver!(4, 2, Day4Part2::day4_part2),
+    solver!(5, 1, Day5Part1::day5_part1),
+    solver!(5, 2, Day5Part2::day5_part2),
+    solver!(6, 1, Day6Part1::day6_part1),
+    solver!(6, 2, Day6Part2::day6_part2),
+    solver!(7, 1, Day7Part1::day7_part1),
+    solver!(7, 2, Day7Part2::day7_part2),
+    solver!(8, 1, Day8Part1::day8_part1),
+    solver!(8, 2, Day8Part2::day8_part2),
+    solver!(9, 1, Day9Part1::day9_part1),
+    solver!(9, 2, Day9Part2::day9_part2),
+    solver!(10, 1, Day10Part1::day10_part1),
+    solver!(10, 2, Day10Part2::day10_part2),
+    solver!(11, 1, Day11Part1::day11_part1),
+    solver!(11, 2, Day11Part2::day11_part2),
+];
+
+/// Every registered solver for `day` (all days if None), optionally just the one part
+pub fn select(day: Option<u32>, part: Option<u32>) -> impl Iterator<Item = &'static Solver> {
+    SOLVERS
+        .iter()
+        .filter(move |s| day.is_none_or(|d| s.day == d) && part.is_none_or(|p| s.part == p))
+}
+
+pub struct RunResult {
+    pub day: u32,
+    pub part: u32,
+    /// The answer, or what went wrong while generating/solving
+    pub answer: Result<String, String>,
+    pub parse_time: Duration,
+    pub solve_time: Duration,
+}
+
+/// Normalise `input` and run the solver on it
+pub fn run(solver: &Solver, input: &str) -> RunResult {
+    let mut result = RunResult {
+        day: solver.day,
+        part: solver.part,
+        answer: Err(String::new()),
+        parse_time: Duration::ZERO,
+        solve_time: Duration::ZERO,
+    };
+
+    let input = input::normalise(input);
+
+    let start = Instant::now();
+    let runner = catch_panic(|| (solver.build)(ArcStr::from(&input)));
+    result.parse_time = start.elapsed();
+    debug!(
+        "day {} part {}: generated in {:?}",
+        solver.day, solver.part, result.parse_time
+    );
+
+    let runner = match runner {
+        Ok(Ok(runner)) => runner,
+        Ok(Err(e)) => {
+            result.answer = Err(format!("parse error: {}", e));
+            return result;
+        }
+        Err(panicked) => {
+            result.answer = Err(panicked);
+            return result;
+        }
+    };
+
+    let start = Instant::now();
+    result.answer = catch_panic(|| runner.try_run()).and_then(|answer| {
+        answer
+            .map(|answer| answer.to_string())
+            .map_err(|e| format!("error: {}", e))
+    });
+    result.solve_time = start.elapsed();
+    debug!(
+        "day {} part {}: solved in {:?}",
+        solver.day, solver.part, result.solve_time
+    );
+
+    result
+}
+
+// A day that's just been scaffolded is all todo!(), that shouldn't take every other day's
+// answers down with it
+fn catch_panic<T>(f: impl FnOnce() -> T) -> Result<T, String> {
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+        let message = match payload.downcast_ref::<&str>() {
+            Some(message) => message.to_string(),
+            None => payload
+                .downcast_ref::<String>()
+                .cloned()
+                .unwrap_or_default(),
+        };
+        format!("panicked: {}", message)
+    })
+}
+
+pub fn format_table(results: &[RunResult]) -> String {
+    let answers = results
+        .iter()
+        .map(|r| match &r.answer {
+            Ok(answer) => answer.clone(),
+            Err(e) => e.clone(),
+        })
+        .collect::<Vec<_>>();
+    let width = answers.iter().map(|a| a.len()).max().unwrap_or(0).max(6);
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "{:>3}  {:>4}  {:<width$}  {:>12}  {:>12}",
+        "Day", "Part", "Answer", "Parse", "Solve"
+    )
+    .unwrap();
+    for (r, answer) in results.iter().zip(answers) {
+        writeln!(
+            out,
+            "{:>3}  {:>4}  {:<width$}  {:>12}  {:>12}",
+            r.day,
+            r.part,
+            answer,
+            format!("{:.2?}", r.parse_time),
+            format!("{:.2?}", r.solve_time),
+        )
+        .unwrap();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn runner_runs_registered_solver() {
+        let solver = select(Some(1), Some(2)).next().unwrap();
+        let result = run(solver, "3   4\n4   3\n2   5\n1   3\n3   9\n3   3\n");
+        assert_eq!(result.answer, Ok("31".to_string()));
+
+        let result = run(solver, "3   4\n4   x\n");
+        assert_eq!(
+            result.answer,
+            Err("parse error: line 2, column 5: invalid digit found in string (at \"x\")".into())
+        );
+    }
+
+    #[test]
+    fn runner_catches_panics() {
+        fn unfinished(_: ArcStr) -> Result<Box<dyn Runner>, Box<dyn Error>> {
+            todo!()
+        }
+        let solver = Solver {
+            day: 12,
+            part: 1,
+            build: unfinished,
+        };
+        let result = run(&solver, "");
+        assert_eq!(
+            result.answer,
+            Err("panicked: not yet implemented".to_string())
+        );
+    }
+
+    #[test]
+    fn runner_normalises_input() {
+        //Windows line endings, a BOM and trailing blank lines used to break the generators
+        let day5 = select(Some(5), Some(1)).next().unwrap();
+        let result = run(
+            day5,
+            "\u{feff}47|53\r\n97|13\r\n97|47\r\n\r\n75,47,53\r\n47,13,97\r\n\r\n",
+        );
+        assert_eq!(result.answer, Ok("47".to_string()));
+
+        let day11 = select(Some(11), Some(1)).next().unwrap();
+        let result = run(day11, "125 17 \n\n");
+        assert_eq!(result.answer, Ok("55312".to_string()));
+    }
+
+    #[test]
+    fn runner_table() {
+        let results = vec![RunResult {
+            day: 6,
+            part: 2,
+            answer: Ok("1234".to_string()),
+            parse_time: Duration::from_micros(5),
+            solve_time: Duration::from_millis(2),
+        }];
+        assert_eq!(
+            format_table(&results),
+            "Day  Part  Answer         Parse         Solve\n  6     2  1234          5.00µs        2.00ms\n"
+        );
+    }
+}
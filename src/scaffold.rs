@@ -0,0 +1,178 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const SOLVERS_START: &str = "pub static SOLVERS: &[Solver] = &[";
+
+#[derive(Debug)]
+pub enum ScaffoldError {
+    AlreadyExists(PathBuf),
+    Io(PathBuf, io::Error),
+    /// A file we need to edit doesn't look the way we expect
+    Unrecognised(PathBuf, &'static str),
+}
+
+impl fmt::Display for ScaffoldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScaffoldError::AlreadyExists(path) => {
+                write!(f, "{} already exists, not overwriting it", path.display())
+            }
+            ScaffoldError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            ScaffoldError::Unrecognised(path, what) => {
+                write!(f, "{}: couldn't find {}", path.display(), what)
+            }
+        }
+    }
+}
+
+impl Error for ScaffoldError {}
+
+fn read(path: PathBuf) -> Result<(PathBuf, String), ScaffoldError> {
+    match fs::read_to_string(&path) {
+        Ok(text) => Ok((path, text)),
+        Err(e) => Err(ScaffoldError::Io(path, e)),
+    }
+}
+
+fn write(path: &Path, contents: &str) -> Result<(), ScaffoldError> {
+    fs::write(path, contents).map_err(|e| ScaffoldError::Io(path.to_path_buf(), e))
+}
+
+pub fn fixture_path(day: u32) -> PathBuf {
+    PathBuf::from(format!("fixtures/day{}.txt", day))
+}
+
+/// Add `pub mod dayN;` after the last day module
+fn register_module(lib: &str, day: u32) -> Option<String> {
+    let is_day_mod = |l: &str| {
+        l.strip_prefix("pub mod day")
+            .and_then(|rest| rest.strip_suffix(';'))
+            .is_some_and(|n| n.parse::<u32>().is_ok())
+    };
+    let mut lines = lib.lines().collect::<Vec<_>>();
+    let last = lines.iter().rposition(|l| is_day_mod(l))?;
+    let module = format!("pub mod day{};", day);
+    lines.insert(last + 1, &module);
+    Some(lines.join("\n") + "\n")
+}
+
+/// Add both parts to the runner's solver table
+fn register_solvers(runner: &str, day: u32) -> Option<String> {
+    let start = runner.find(SOLVERS_START)?;
+    let end = start + runner[start..].find("];")?;
+    let entries = (1..=2)
+        .map(|part| {
+            format!(
+                "    solver!({day}, {part}, Day{day}Part{part}::day{day}_part{part}),\n",
+                day = day,
+                part = part
+            )
+        })
+        .collect::<String>();
+    Some(format!("{}{}{}", &runner[..end], entries, &runner[end..]))
+}
+
+/// Create `src/dayN.rs` from `src/template.rs`, register it in `lib.rs` and the runner, and
+/// create an empty example input for its tests. Paths are relative to `root` (the crate root).
+/// Everything is checked before anything is written. Returns the files created.
+pub fn new_day(root: &Path, day: u32) -> Result<Vec<PathBuf>, ScaffoldError> {
+    let day_path = root.join(format!("src/day{}.rs", day));
+    let fixture = root.join(fixture_path(day));
+    for path in [&day_path, &fixture] {
+        if path.exists() {
+            return Err(ScaffoldError::AlreadyExists(path.clone()));
+        }
+    }
+
+    let (_, template) = read(root.join("src/template.rs"))?;
+    let (lib_path, lib) = read(root.join("src/lib.rs"))?;
+    let (runner_path, runner) = read(root.join("src/runner.rs"))?;
+
+    if lib.lines().any(|l| l == format!("pub mod day{};", day)) {
+        return Err(ScaffoldError::AlreadyExists(lib_path));
+    }
+
+    let source = template.replace("dayX", &format!("day{}", day));
+    let lib = register_module(&lib, day).ok_or(ScaffoldError::Unrecognised(
+        lib_path.clone(),
+        "any `pub mod dayN;`",
+    ))?;
+    let runner = register_solvers(&runner, day).ok_or(ScaffoldError::Unrecognised(
+        runner_path.clone(),
+        "the SOLVERS table",
+    ))?;
+
+    if let Some(dir) = fixture.parent() {
+        fs::create_dir_all(dir).map_err(|e| ScaffoldError::Io(dir.to_path_buf(), e))?;
+    }
+    write(&fixture, "")?;
+    write(&day_path, &source)?;
+    write(&lib_path, &lib)?;
+    write(&runner_path, &runner)?;
+
+    Ok(vec![day_path, fixture])
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn scratch_crate(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("aoc2024-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(
+            root.join("src/template.rs"),
+            "#[aoc_generator(dayX)]\nfn dayX_parse(input: &str) {}\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("src/lib.rs"),
+            "pub mod grid;\n\npub mod day1;\npub mod day2;\n\naoc_lib! { year = 2024 }\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("src/runner.rs"),
+            "pub static SOLVERS: &[Solver] = &[\n    solver!(1, 1, Day1Part1::day1_part1),\n];\n",
+        )
+        .unwrap();
+        root
+    }
+
+    #[test]
+    fn scaffold_new_day() {
+        let root = scratch_crate("scaffold");
+
+        new_day(&root, 3).unwrap();
+
+        let source = fs::read_to_string(root.join("src/day3.rs")).unwrap();
+        assert_eq!(
+            source,
+            "#[aoc_generator(day3)]\nfn day3_parse(input: &str) {}\n"
+        );
+        let lib = fs::read_to_string(root.join("src/lib.rs")).unwrap();
+        assert_eq!(
+            lib,
+            "pub mod grid;\n\npub mod day1;\npub mod day2;\npub mod day3;\n\naoc_lib! { year = 2024 }\n"
+        );
+        let runner = fs::read_to_string(root.join("src/runner.rs")).unwrap();
+        assert!(runner.ends_with(
+            "    solver!(3, 1, Day3Part1::day3_part1),\n    solver!(3, 2, Day3Part2::day3_part2),\n];\n"
+        ));
+        assert_eq!(
+            fs::read_to_string(root.join("fixtures/day3.txt")).unwrap(),
+            ""
+        );
+
+        //Second time round it has to refuse, and leave everything alone
+        let err = new_day(&root, 3).unwrap_err();
+        assert!(matches!(err, ScaffoldError::AlreadyExists(_)));
+        assert_eq!(fs::read_to_string(root.join("src/lib.rs")).unwrap(), lib);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
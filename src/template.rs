@@ -1,18 +1,20 @@
-type InputType = Vec<&str>;
+use crate::parse::ParseError;
+
+type InputType = Vec<String>;
 type OutputType = u64;
 
 #[aoc_generator(dayX)]
-fn dayX_parse(input: &str) -> InputType {
+fn dayX_parse(_input: &str) -> Result<InputType, ParseError> {
     todo!();
 }
 
 #[aoc(dayX, part1)]
-pub fn part1(input: &InputType) -> OutputType {
+pub fn part1(_input: &InputType) -> OutputType {
     todo!();
 }
 
 #[aoc(dayX, part2)]
-pub fn part2(input: &InputType) -> OutputType {
+pub fn part2(_input: &InputType) -> OutputType {
     todo!();
 }
 
@@ -22,16 +24,16 @@ mod tests {
     use super::*;
 
     fn get_test_input() -> &'static str {
-        todo!();
+        include_str!("../fixtures/dayX.txt")
     }
 
     #[test]
     fn dayX_part1() {
-        assert_eq!(part1(&dayX_parse(get_test_input())), 0);
+        assert_eq!(part1(&dayX_parse(get_test_input()).unwrap()), 0);
     }
 
     #[test]
     fn dayX_part2() {
-        assert_eq!(part2(&dayX_parse(get_test_input())), 0);
+        assert_eq!(part2(&dayX_parse(get_test_input()).unwrap()), 0);
     }
 }